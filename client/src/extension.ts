@@ -16,6 +16,7 @@ import {
   TextEdit,
   Selection,
   Uri,
+  FoldingRangeKind,
 } from "vscode";
 
 import {
@@ -24,39 +25,134 @@ import {
   LanguageClient,
   LanguageClientOptions,
   ServerOptions,
+  StreamInfo,
 } from "vscode-languageclient/node";
 
+import * as fs from "fs";
+import * as net from "net";
+import * as path from "path";
+
 let client: LanguageClient;
 
+// Documents whose data payloads have been folded since they were opened
+const folded = new Set<string>();
+
+// Payload lines of documents not shown in the active editor when their folding ranges came
+// back. `editor.fold` only acts on the active editor, so they are folded once it shows them.
+const pendingFolds = new Map<string, number[]>();
+
+function foldPayloads(selectionLines: number[]) {
+  commands.executeCommand("editor.fold", { selectionLines, levels: 1 });
+}
+
+// Locate the server binary: the `ofoam_ls.server.path` setting, then `OFOAM_LS_PATH`,
+// then a cargo build inside the extension folder, then whatever is on `PATH`.
+function serverCommand(context: ExtensionContext): string {
+  const configured = workspace
+    .getConfiguration("ofoam_ls")
+    .get<string>("server.path");
+  if (configured) {
+    return configured;
+  }
+  if (process.env.OFOAM_LS_PATH) {
+    return process.env.OFOAM_LS_PATH;
+  }
+  const exe = process.platform === "win32" ? "ofoam_ls.exe" : "ofoam_ls";
+  for (const profile of ["release", "debug"]) {
+    const candidate = context.asAbsolutePath(path.join("target", profile, exe));
+    if (fs.existsSync(candidate)) {
+      return candidate;
+    }
+  }
+  return exe;
+}
+
 export async function activate(context: ExtensionContext) {
-  // const traceOutputChannel = window.createOutputChannel("");
-  const command = "/Users/laurence/OpenFOAM-LSP/target/release/ofoam_ls";
+  const traceOutputChannel = window.createOutputChannel("OpenFOAM LSP trace");
+  const config = workspace.getConfiguration("ofoam_ls");
+  const args = ["--stdio"];
+  const logFile = config.get<string>("server.logFile");
+  if (logFile) {
+    args.push("--log-file", logFile);
+  }
   const run: Executable = {
-    command,
+    command: serverCommand(context),
+    args,
     options: {
       env: {
         ...process.env,
-        RUST_LOG: "debug",
+        RUST_LOG: process.env.RUST_LOG ?? "ofoam_ls=debug",
       },
     },
   };
-  const serverOptions: ServerOptions = {
-    run,
-    debug: run,
-  };
+
+  // When a port is configured, attach to a server started separately with `ofoam_ls --listen <port>`
+  const port = config.get<number>("server.port");
+  const serverOptions: ServerOptions = port
+    ? () =>
+        new Promise<StreamInfo>((resolve, reject) => {
+          const socket = net.connect({ host: "127.0.0.1", port }, () =>
+            resolve({ reader: socket, writer: socket }),
+          );
+          socket.on("error", reject);
+        })
+    : {
+        run,
+        debug: run,
+      };
   // If the extension is launched in debug mode then the debug server options are used
   // Otherwise the run options are used
   // Options to control the language client
   let clientOptions: LanguageClientOptions = {
-    // Register the server for plain text documents
+    // OpenFOAM files have no common extension; the server ignores files that are not part of a case
     documentSelector: [{ scheme: "file", language: "*" }],
-    synchronize: {
-      // Notify the server about file changes to '.clientrc files contained in the workspace
-      fileEvents: workspace.createFileSystemWatcher("**/.clientrc"),
+    // The server registers its own file watchers for case files and included files
+    traceOutputChannel,
+    middleware: {
+      // Fold the data payloads the first time a file is shown
+      provideFoldingRanges: async (document, context, token, next) => {
+        const ranges = await next(document, context, token);
+        const key = document.uri.toString();
+        if (
+          ranges &&
+          !folded.has(key) &&
+          workspace
+            .getConfiguration("ofoam_ls")
+            .get<boolean>("folding.collapseData", true)
+        ) {
+          folded.add(key);
+          const selectionLines = ranges
+            // The server marks data payloads, and nothing else, as regions
+            .filter((range) => range.kind === FoldingRangeKind.Region)
+            .map((range) => range.start);
+          if (selectionLines.length > 0) {
+            if (window.activeTextEditor?.document === document) {
+              foldPayloads(selectionLines);
+            } else {
+              pendingFolds.set(key, selectionLines);
+            }
+          }
+        }
+        return ranges;
+      },
     },
-    // traceOutputChannel,
   };
 
+  context.subscriptions.push(
+    workspace.onDidCloseTextDocument((document) => {
+      folded.delete(document.uri.toString());
+      pendingFolds.delete(document.uri.toString());
+    }),
+    window.onDidChangeActiveTextEditor((editor) => {
+      const key = editor?.document.uri.toString();
+      const selectionLines = key && pendingFolds.get(key);
+      if (key && selectionLines) {
+        pendingFolds.delete(key);
+        foldPayloads(selectionLines);
+      }
+    }),
+  );
+
   // Create the language client and start the client.
   client = new LanguageClient(
     "ofoam_ls",
@@ -64,7 +160,6 @@ export async function activate(context: ExtensionContext) {
     serverOptions,
     clientOptions,
   );
-  // activateInlayHints(context);
   client.start();
 }
 
@@ -0,0 +1,694 @@
+use crate::parser::{Span, Token};
+
+/// A word-like piece of source: a key, a bare word, a string or a macro name.
+///
+/// `text` is the source text with any surrounding quotes removed, while `span` covers the quotes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Word {
+    pub text: String,
+    pub span: Span,
+    pub quoted: bool,
+}
+
+/// A brace-delimited dictionary, or the top level of a file.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Dictionary {
+    pub entries: Vec<Entry>,
+    /// Covers the braces, or the whole text for the top level.
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Entry {
+    /// `key { ... }`
+    Dict {
+        key: Word,
+        dict: Dictionary,
+        span: Span,
+    },
+    /// `key value ...;`
+    Value {
+        key: Word,
+        values: Vec<Value>,
+        span: Span,
+    },
+    /// `#include "file"`, `#includeFunc name`, `#remove key`, ...
+    Directive {
+        name: Word,
+        args: Vec<Value>,
+        span: Span,
+    },
+    /// `$name;`, merging the entries of another dictionary into this one
+    Macro { name: Word, span: Span },
+    /// Values without a key, such as the patch list making up `polyMesh/boundary`
+    Bare { values: Vec<Value>, span: Span },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Word(Word),
+    String(Word),
+    Number(f64, Span),
+    Macro(Word),
+    Directive(Word),
+    Verbatim(Span),
+    /// `( ... )`
+    List {
+        items: Vec<Value>,
+        span: Span,
+    },
+    /// `[ ... ]`
+    Dimensions {
+        items: Vec<Value>,
+        span: Span,
+    },
+    /// `{ ... }` appearing as a value, e.g. in the `boundary` list of `blockMeshDict`
+    Dict(Dictionary),
+    /// Punctuation or characters that have no meaning on their own
+    Other(Span),
+}
+
+impl Entry {
+    pub fn key(&self) -> Option<&Word> {
+        match self {
+            Entry::Dict { key, .. } | Entry::Value { key, .. } => Some(key),
+            _ => None,
+        }
+    }
+
+    /// The value of a `key word;` entry, e.g. the `fixedValue` of `type fixedValue;`.
+    pub fn word(&self) -> Option<&Word> {
+        match self {
+            Entry::Value { values, .. } => match values.first()? {
+                Value::Word(word) | Value::String(word) => Some(word),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Entry::Dict { span, .. }
+            | Entry::Value { span, .. }
+            | Entry::Directive { span, .. }
+            | Entry::Macro { span, .. }
+            | Entry::Bare { span, .. } => *span,
+        }
+    }
+}
+
+impl Value {
+    pub fn span(&self) -> Span {
+        match self {
+            Value::Word(word)
+            | Value::String(word)
+            | Value::Macro(word)
+            | Value::Directive(word) => word.span,
+            Value::Number(_, span)
+            | Value::Verbatim(span)
+            | Value::List { span, .. }
+            | Value::Dimensions { span, .. }
+            | Value::Other(span) => *span,
+            Value::Dict(dict) => dict.span,
+        }
+    }
+}
+
+impl Dictionary {
+    /// The last entry with this key; later entries override earlier ones in OpenFOAM.
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.key().is_some_and(|k| k.text == key))
+    }
+
+    /// The sub-dictionary with this key.
+    pub fn dict(&self, key: &str) -> Option<&Dictionary> {
+        match self.get(key)? {
+            Entry::Dict { dict, .. } => Some(dict),
+            _ => None,
+        }
+    }
+
+    /// The dictionaries whose braces contain `offset`, from the top level inwards.
+    pub fn scopes_at(&self, offset: usize) -> Vec<&Dictionary> {
+        let mut scopes = vec![self];
+        let mut dict = self;
+        while let Some((_, inner)) = dict.child_at(offset) {
+            scopes.push(inner);
+            dict = inner;
+        }
+        scopes
+    }
+
+    /// Keys of the dictionaries enclosing `offset`, e.g. `["boundaryField", "inlet"]`.
+    ///
+    /// Only dictionaries whose braces contain the offset count, so a cursor on the key
+    /// `inlet` itself is inside `boundaryField` but not inside `inlet`.
+    pub fn path_at(&self, offset: usize) -> Vec<&str> {
+        let mut path = Vec::new();
+        let mut dict = self;
+        while let Some((name, inner)) = dict.child_at(offset) {
+            path.push(name);
+            dict = inner;
+        }
+        path
+    }
+
+    /// The dictionary directly inside this one whose braces contain `offset`, with its name.
+    /// Dictionaries in lists, such as the patches of `blockMeshDict`, are named by the word
+    /// before them.
+    fn child_at(&self, offset: usize) -> Option<(&str, &Dictionary)> {
+        let inside = |dict: &Dictionary| dict.span.start < offset && offset < dict.span.end;
+        self.entries.iter().find_map(|entry| match entry {
+            Entry::Dict { key, dict, .. } if inside(dict) => Some((key.text.as_str(), dict)),
+            Entry::Value { values, .. } | Entry::Bare { values, .. } => {
+                dict_in_values(values, &inside)
+            }
+            _ => None,
+        })
+    }
+
+    /// The entry a macro such as `$internalField`, `${U}` or `$:outer.inner` refers to, as
+    /// seen from `offset`. Plain names are looked up from the innermost enclosing dictionary
+    /// outwards; names starting with `:` from the top level, with `.` or `/` separating keys.
+    pub fn resolve_macro(&self, name: &str, offset: usize) -> Option<&Entry> {
+        let name = name.trim_start_matches('$');
+        let name = name
+            .strip_prefix('{')
+            .and_then(|name| name.strip_suffix('}'))
+            .unwrap_or(name);
+
+        if let Some(path) = name.strip_prefix(':') {
+            return self.lookup_path(path);
+        }
+        self.scopes_at(offset)
+            .into_iter()
+            .rev()
+            .find_map(|scope| scope.get(name).or_else(|| scope.lookup_path(name)))
+    }
+
+    fn lookup_path(&self, path: &str) -> Option<&Entry> {
+        if let Some(entry) = self.get(path) {
+            return Some(entry);
+        }
+        let (first, rest) = path.split_once(['.', '/'])?;
+        match self.get(first)? {
+            Entry::Dict { dict, .. } => dict.lookup_path(rest),
+            _ => None,
+        }
+    }
+
+    /// The key, word, string or macro at `offset`, looking into nested dictionaries and lists.
+    pub fn word_at(&self, offset: usize) -> Option<WordAt<'_>> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| contains(entry.span(), offset))?;
+        let at = |word| WordAt {
+            entry,
+            word,
+            value: None,
+        };
+        match entry {
+            Entry::Dict { key, dict, .. } => {
+                if contains(key.span, offset) {
+                    Some(at(key))
+                } else {
+                    dict.word_at(offset)
+                }
+            }
+            Entry::Value {
+                key: name, values, ..
+            }
+            | Entry::Directive {
+                name, args: values, ..
+            } => {
+                if contains(name.span, offset) {
+                    Some(at(name))
+                } else {
+                    word_in_values(entry, values, offset)
+                }
+            }
+            Entry::Bare { values, .. } => word_in_values(entry, values, offset),
+            Entry::Macro { name, .. } => Some(at(name)),
+        }
+    }
+}
+
+fn dict_in_values<'a>(
+    values: &'a [Value],
+    inside: &impl Fn(&Dictionary) -> bool,
+) -> Option<(&'a str, &'a Dictionary)> {
+    values
+        .iter()
+        .enumerate()
+        .find_map(|(i, value)| match value {
+            Value::Dict(dict) if inside(dict) => {
+                let name = match i.checked_sub(1).map(|previous| &values[previous]) {
+                    Some(Value::Word(word)) => word.text.as_str(),
+                    _ => "",
+                };
+                Some((name, dict))
+            }
+            Value::List { items, .. } => dict_in_values(items, inside),
+            _ => None,
+        })
+}
+
+/// A word under the cursor and the entry it belongs to.
+#[derive(Debug)]
+pub struct WordAt<'a> {
+    pub entry: &'a Entry,
+    pub word: &'a Word,
+    /// The value the word is, or `None` for a key, a directive name or a `$name;` entry
+    pub value: Option<&'a Value>,
+}
+
+fn word_in_values<'a>(entry: &'a Entry, values: &'a [Value], offset: usize) -> Option<WordAt<'a>> {
+    let value = values.iter().find(|value| contains(value.span(), offset))?;
+    match value {
+        Value::Word(word) | Value::String(word) | Value::Macro(word) | Value::Directive(word) => {
+            Some(WordAt {
+                entry,
+                word,
+                value: Some(value),
+            })
+        }
+        Value::List { items, .. } | Value::Dimensions { items, .. } => {
+            word_in_values(entry, items, offset)
+        }
+        Value::Dict(dict) => dict.word_at(offset),
+        Value::Number(..) | Value::Verbatim(_) | Value::Other(_) => None,
+    }
+}
+
+/// The dictionaries named by the word before them in the lists among `values`, such as the
+/// patches in the `boundary` list of `blockMeshDict`.
+pub fn named_dicts(values: &[Value]) -> Vec<(&Word, &Dictionary)> {
+    let mut named = Vec::new();
+    for value in values {
+        if let Value::List { items, .. } = value {
+            for pair in items.windows(2) {
+                if let [Value::Word(name), Value::Dict(dict)] = pair {
+                    named.push((name, dict));
+                }
+            }
+        }
+    }
+    named
+}
+
+/// Whether `offset` lies within `span`, counting the position just after the last character.
+pub fn contains(span: Span, offset: usize) -> bool {
+    span.start <= offset && offset <= span.end
+}
+
+/// A syntax error found while building the tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub span: Span,
+    pub message: String,
+}
+
+/// Build the tree for a scanned file.
+pub fn parse(text: &str, tokens: &[Token], spans: &[Span]) -> (Dictionary, Vec<SyntaxError>) {
+    let significant: Vec<(Token, Span)> = tokens
+        .iter()
+        .zip(spans.iter())
+        .filter(|(token, _)| !matches!(token, Token::BlockComment | Token::LineComment))
+        .map(|(token, span)| (*token, *span))
+        .collect();
+
+    let mut parser = TreeBuilder {
+        text,
+        tokens: &significant,
+        position: 0,
+        errors: Vec::new(),
+    };
+
+    let entries = parser.entries(false);
+    let dictionary = Dictionary {
+        entries,
+        span: Span {
+            start: 0,
+            end: text.len(),
+        },
+    };
+    (dictionary, parser.errors)
+}
+
+struct TreeBuilder<'a> {
+    text: &'a str,
+    tokens: &'a [(Token, Span)],
+    position: usize,
+    errors: Vec<SyntaxError>,
+}
+
+impl TreeBuilder<'_> {
+    fn peek(&self) -> Option<(Token, Span)> {
+        self.tokens.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<(Token, Span)> {
+        let token = self.peek();
+        self.position += 1;
+        token
+    }
+
+    fn error(&mut self, span: Span, message: impl Into<String>) {
+        self.errors.push(SyntaxError {
+            span,
+            message: message.into(),
+        });
+    }
+
+    fn word(&self, token: Token, span: Span) -> Word {
+        let raw = &self.text[span.start..span.end];
+        if token == Token::String {
+            let inner = raw.strip_prefix('"').unwrap_or(raw);
+            Word {
+                text: inner.strip_suffix('"').unwrap_or(inner).to_string(),
+                span,
+                quoted: true,
+            }
+        } else {
+            Word {
+                text: raw.to_string(),
+                span,
+                quoted: false,
+            }
+        }
+    }
+
+    /// The end of the previous token, used to close spans.
+    fn last_end(&self) -> usize {
+        self.position
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map_or(0, |(_, span)| span.end)
+    }
+
+    /// Parse entries up to the closing brace when `nested`, or the end of input otherwise.
+    fn entries(&mut self, nested: bool) -> Vec<Entry> {
+        let mut entries = Vec::new();
+
+        while let Some((token, span)) = self.peek() {
+            match token {
+                Token::RightBrace if nested => break,
+                Token::RightBrace => {
+                    self.next();
+                    self.error(span, "Unexpected '}'");
+                }
+                Token::Semicolon => {
+                    self.next();
+                }
+                Token::Directive => {
+                    self.next();
+                    let name = self.word(token, span);
+                    let args = match self.peek() {
+                        Some((next, _)) if Self::starts_value(next) => vec![self.value()],
+                        _ => Vec::new(),
+                    };
+                    if matches!(self.peek(), Some((Token::Semicolon, _))) {
+                        self.next();
+                    }
+                    entries.push(Entry::Directive {
+                        name,
+                        args,
+                        span: Span {
+                            start: span.start,
+                            end: self.last_end(),
+                        },
+                    });
+                }
+                Token::Macro => {
+                    self.next();
+                    let name = self.word(token, span);
+                    if matches!(self.peek(), Some((Token::Semicolon, _))) {
+                        self.next();
+                    }
+                    entries.push(Entry::Macro {
+                        name,
+                        span: Span {
+                            start: span.start,
+                            end: self.last_end(),
+                        },
+                    });
+                }
+                token if token.is_word() || token == Token::String => {
+                    self.next();
+                    let key = self.word(token, span);
+                    entries.push(self.keyed_entry(key));
+                }
+                _ => {
+                    let values = self.values_until_semicolon();
+                    entries.push(Entry::Bare {
+                        values,
+                        span: Span {
+                            start: span.start,
+                            end: self.last_end(),
+                        },
+                    });
+                }
+            }
+        }
+
+        entries
+    }
+
+    fn keyed_entry(&mut self, key: Word) -> Entry {
+        let start = key.span.start;
+
+        if let Some((Token::LeftBrace, brace)) = self.peek() {
+            let dict = self.dictionary(brace);
+            return Entry::Dict {
+                key,
+                span: Span {
+                    start,
+                    end: dict.span.end,
+                },
+                dict,
+            };
+        }
+
+        let values = self.values_until_semicolon();
+        Entry::Value {
+            key,
+            values,
+            span: Span {
+                start,
+                end: self.last_end(),
+            },
+        }
+    }
+
+    /// Parse `{ ... }`, starting at the opening brace.
+    fn dictionary(&mut self, open: Span) -> Dictionary {
+        self.next();
+        let entries = self.entries(true);
+        let end = match self.peek() {
+            Some((Token::RightBrace, close)) => {
+                self.next();
+                close.end
+            }
+            _ => {
+                self.error(open, "Missing '}'");
+                self.last_end()
+            }
+        };
+        Dictionary {
+            entries,
+            span: Span {
+                start: open.start,
+                end,
+            },
+        }
+    }
+
+    /// Values up to and including the terminating semicolon.
+    fn values_until_semicolon(&mut self) -> Vec<Value> {
+        let mut values = Vec::new();
+        loop {
+            match self.peek() {
+                Some((Token::Semicolon, _)) => {
+                    self.next();
+                    break;
+                }
+                Some((Token::RightBrace, span)) => {
+                    self.error(span, "Missing ';'");
+                    break;
+                }
+                None => {
+                    // Trailing lists such as the one in `polyMesh/boundary` need no semicolon
+                    if !matches!(values.last(), Some(Value::List { .. }) | None) {
+                        let end = self.last_end();
+                        self.error(Span { start: end, end }, "Missing ';'");
+                    }
+                    break;
+                }
+                Some(_) => values.push(self.value()),
+            }
+        }
+        values
+    }
+
+    fn starts_value(token: Token) -> bool {
+        !matches!(token, Token::Semicolon | Token::RightBrace)
+    }
+
+    fn value(&mut self) -> Value {
+        let Some((token, span)) = self.next() else {
+            let end = self.last_end();
+            return Value::Other(Span { start: end, end });
+        };
+
+        match token {
+            Token::String => Value::String(self.word(token, span)),
+            Token::Int(value) => Value::Number(value as f64, span),
+            Token::Float(value) => Value::Number(value, span),
+            Token::Macro => Value::Macro(self.word(token, span)),
+            Token::Directive => Value::Directive(self.word(token, span)),
+            Token::Verbatim => Value::Verbatim(span),
+            Token::LeftParen => {
+                let items = self.items_until(Token::RightParen, span);
+                Value::List {
+                    items,
+                    span: Span {
+                        start: span.start,
+                        end: self.last_end(),
+                    },
+                }
+            }
+            Token::LeftBracket => {
+                let items = self.items_until(Token::RightBracket, span);
+                Value::Dimensions {
+                    items,
+                    span: Span {
+                        start: span.start,
+                        end: self.last_end(),
+                    },
+                }
+            }
+            Token::LeftBrace => {
+                self.position -= 1;
+                Value::Dict(self.dictionary(span))
+            }
+            token if token.is_word() => Value::Word(self.word(token, span)),
+            _ => Value::Other(span),
+        }
+    }
+
+    /// Items of a list or dimension set up to and including `close`.
+    fn items_until(&mut self, close: Token, open: Span) -> Vec<Value> {
+        let mut items = Vec::new();
+        loop {
+            match self.peek() {
+                Some((token, _)) if token == close => {
+                    self.next();
+                    break;
+                }
+                Some((Token::Semicolon | Token::RightBrace, _)) | None => {
+                    let expected = if close == Token::RightParen { ')' } else { ']' };
+                    self.error(open, format!("Missing '{expected}'"));
+                    break;
+                }
+                Some(_) => items.push(self.value()),
+            }
+        }
+        items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::scan;
+
+    fn parse_text(text: &str) -> (Dictionary, Vec<SyntaxError>) {
+        let (_, (tokens, spans)) = scan(text).unwrap();
+        parse(text, &tokens, &spans)
+    }
+
+    #[test]
+    fn test_parse_field_file() {
+        let text = "dimensions [0 1 -1 0 0 0 0];\ninternalField uniform (0 0 0);\nboundaryField\n{\n    \"(inlet|outlet)\"\n    {\n        type fixedValue;\n        value $internalField;\n    }\n    #includeEtc \"caseDicts/setConstraintTypes\"\n}\n";
+        let (root, errors) = parse_text(text);
+
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(root.entries.len(), 3);
+        assert!(matches!(
+            &root.entries[0],
+            Entry::Value { values, .. } if matches!(&values[..], [Value::Dimensions { items, .. }] if items.len() == 7)
+        ));
+
+        let Entry::Dict { dict: patches, .. } = &root.entries[2] else {
+            panic!("boundaryField is not a dictionary");
+        };
+        let Entry::Dict {
+            key, dict: inlet, ..
+        } = &patches.entries[0]
+        else {
+            panic!("patch is not a dictionary");
+        };
+        assert!(key.quoted);
+        assert_eq!(key.text, "(inlet|outlet)");
+        assert!(
+            matches!(&inlet.entries[1], Entry::Value { values, .. } if matches!(&values[..], [Value::Macro(_)]))
+        );
+        assert!(matches!(patches.entries[1], Entry::Directive { .. }));
+    }
+
+    #[test]
+    fn test_recovers_from_missing_semicolon_and_brace() {
+        let text = "a\n{\n    type fixedValue\n}\nb\n{\n    c 1;\n";
+        let (root, errors) = parse_text(text);
+
+        assert_eq!(root.entries.len(), 2);
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| e.message.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Missing ';'", "Missing '}'"]
+        );
+        assert!(matches!(&root.entries[1], Entry::Dict { dict, .. } if dict.entries.len() == 1));
+    }
+
+    #[test]
+    fn test_resolve_macros_from_inner_scopes_outwards() {
+        let text = "a 1;\nouter\n{\n    a 2;\n    inner { b $a; }\n}\nc $:outer.a;\n";
+        let (root, _) = parse_text(text);
+
+        let value = |entry: Option<&Entry>| match entry {
+            Some(Entry::Value { values, .. }) => match values.first() {
+                Some(Value::Number(number, _)) => Some(*number),
+                _ => None,
+            },
+            _ => None,
+        };
+        let inside = text.find("$a").unwrap();
+        assert_eq!(value(root.resolve_macro("$a", inside)), Some(2.0));
+        assert_eq!(value(root.resolve_macro("$a", 0)), Some(1.0));
+        assert_eq!(value(root.resolve_macro("$:outer.a", 0)), Some(2.0));
+        assert_eq!(value(root.resolve_macro("${a}", 0)), Some(1.0));
+    }
+
+    #[test]
+    fn test_word_at_finds_keys_values_and_macros() {
+        let text = "boundary\n(\n    walls { type wall; }\n);\nb { c $a; }\n";
+        let (root, _) = parse_text(text);
+
+        let word = |needle: &str| {
+            let at = root.word_at(text.find(needle).unwrap() + 1).unwrap();
+            (at.word.text.clone(), at.value.is_some())
+        };
+        assert_eq!(word("walls"), ("walls".to_string(), true));
+        assert_eq!(word("type"), ("type".to_string(), false));
+        assert_eq!(word("wall;"), ("wall".to_string(), true));
+        assert_eq!(word("$a"), ("$a".to_string(), true));
+        assert_eq!(word("c $a"), ("c".to_string(), false));
+    }
+}
@@ -0,0 +1,611 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::ast::{Dictionary, Entry, Value};
+use crate::document::Document;
+use crate::file_kind::FileKind;
+
+/// Files larger than this are mesh or field data rather than dictionaries worth indexing.
+const MAX_INDEXED_FILE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// How deep below a workspace folder to look for cases.
+const MAX_DISCOVERY_DEPTH: usize = 8;
+
+/// Extensions of geometry and data files that live inside cases but are not dictionaries.
+const DATA_EXTENSIONS: &[&str] = &[
+    "stl", "obj", "vtk", "vtp", "gz", "eMesh", "csv", "dat", "xy",
+];
+
+/// An OpenFOAM case: a directory containing `system/controlDict`, and its parsed files.
+#[derive(Debug)]
+pub struct Case {
+    pub root: PathBuf,
+    pub files: HashMap<PathBuf, Document>,
+    /// The time directories whose fields are in `files`
+    pub time_dirs: Vec<PathBuf>,
+    /// The files each file in `files` includes, kept in step by `insert` and `remove`
+    includes: HashMap<PathBuf, Vec<PathBuf>>,
+    /// The files including each included file
+    included_by: HashMap<PathBuf, Vec<PathBuf>>,
+}
+
+impl Case {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            files: HashMap::new(),
+            time_dirs: Vec::new(),
+            includes: HashMap::new(),
+            included_by: HashMap::new(),
+        }
+    }
+
+    pub fn is_case_dir(dir: &Path) -> bool {
+        dir.join("system").join("controlDict").is_file()
+    }
+
+    /// The case directory containing `path`, if any.
+    pub fn root_of(path: &Path) -> Option<PathBuf> {
+        path.ancestors()
+            .find(|dir| Self::is_case_dir(dir))
+            .map(Path::to_path_buf)
+    }
+
+    /// Every case at or below `folder`. The search does not descend into a case once found,
+    /// nor into hidden directories.
+    pub fn discover(folder: &Path) -> Vec<PathBuf> {
+        let mut cases = Vec::new();
+        discover_below(folder, MAX_DISCOVERY_DEPTH, &mut cases);
+        cases
+    }
+
+    /// Time directories are named by a number, e.g. `0`, `0.5` or `1e-05`. `0.orig` holds the
+    /// pristine initial conditions and is treated the same way.
+    pub fn is_time_dir(name: &str) -> bool {
+        let time = name.strip_suffix(".orig").unwrap_or(name);
+        time.chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+            && time.parse::<f64>().is_ok_and(f64::is_finite)
+    }
+
+    /// The files making up the case: everything in `system` and `constant`, and the fields in
+    /// the initial time directories. Of the mesh only `polyMesh/boundary` is included, and
+    /// decomposed `processor*` directories are skipped as they duplicate the case.
+    pub fn files_to_index(root: &Path) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        collect_files(&root.join("system"), &mut files);
+        collect_files(&root.join("constant"), &mut files);
+        for dir in Self::initial_time_dirs(root) {
+            files.extend(Self::time_dir_files(&dir));
+        }
+
+        files.retain(|path| Self::is_indexable(path));
+        files
+    }
+
+    /// The time directories indexing reads: `0`, `0.orig` and the earliest one. The others
+    /// mostly hold results and are read when one of their files is opened.
+    pub fn initial_time_dirs(root: &Path) -> Vec<PathBuf> {
+        let time_dirs: Vec<_> = read_dir(root)
+            .into_iter()
+            .filter(|path| path.is_dir())
+            .filter_map(|path| {
+                let name = path.file_name()?.to_str()?;
+                let time = name.parse::<f64>().ok().filter(|_| Self::is_time_dir(name));
+                let initial = name == "0" || name == "0.orig";
+                (initial || time.is_some()).then(|| (path.clone(), time, initial))
+            })
+            .collect();
+        let earliest = time_dirs
+            .iter()
+            .filter_map(|(path, time, _)| Some((time.as_ref()?, path)))
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, path)| path.clone());
+
+        let mut dirs: Vec<_> = time_dirs
+            .into_iter()
+            .filter(|(path, _, initial)| *initial || Some(path) == earliest.as_ref())
+            .map(|(path, _, _)| path)
+            .collect();
+        dirs.sort();
+        dirs
+    }
+
+    /// The files worth indexing in time directory `dir`.
+    pub fn time_dir_files(dir: &Path) -> Vec<PathBuf> {
+        read_dir(dir)
+            .into_iter()
+            .filter(|path| path.is_file() && Self::is_indexable(path))
+            .collect()
+    }
+
+    /// The time directory holding `path` when it is a field file of the case but its time
+    /// directory has not been read.
+    pub fn unread_time_dir(&self, path: &Path) -> Option<PathBuf> {
+        let dir = path.parent()?;
+        (self.is_field_file(path) && !self.time_dirs.iter().any(|read| read == dir))
+            .then(|| dir.to_path_buf())
+    }
+
+    /// Whether `path` is part of the case layout that `files_to_index` reads: below `system` or
+    /// `constant`, or directly inside a time directory.
+    pub fn is_case_file(&self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        let components: Vec<_> = relative
+            .components()
+            .filter_map(|component| component.as_os_str().to_str())
+            .collect();
+        match components.as_slice() {
+            ["constant", .., "polyMesh", name] => *name == "boundary",
+            ["system" | "constant", _, ..] => true,
+            [dir, _] => Self::is_time_dir(dir),
+            _ => false,
+        }
+    }
+
+    fn is_indexable(path: &Path) -> bool {
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        let data = path
+            .extension()
+            .is_some_and(|extension| DATA_EXTENSIONS.iter().any(|data| extension == *data));
+        let small =
+            std::fs::metadata(path).is_ok_and(|metadata| metadata.len() <= MAX_INDEXED_FILE_SIZE);
+        !hidden && !data && small
+    }
+
+    /// Read and parse a file from disk, if it is an OpenFOAM file.
+    pub fn load(path: &Path) -> Option<Document> {
+        Self::read(path, None)
+    }
+
+    /// Included files are dictionaries whatever their name or place.
+    fn load_included(path: &Path) -> Option<Document> {
+        Self::read(path, Some(FileKind::Dictionary))
+    }
+
+    fn read(path: &Path, fallback: Option<FileKind>) -> Option<Document> {
+        let text = std::fs::read_to_string(path).ok()?;
+        let kind = FileKind::detect(path, &text).or(fallback)?;
+        Some(Document::new(text, 0, kind))
+    }
+
+    /// Add or replace the file at `path`, recording what it includes.
+    pub fn insert(&mut self, path: PathBuf, document: Document) {
+        self.unlink(&path);
+        let mut names = Vec::new();
+        collect_includes(&document.ast, &mut names);
+        let includes: Vec<_> = names
+            .iter()
+            .map(|name| self.include_path(&path, name))
+            .collect();
+        for include in &includes {
+            self.included_by
+                .entry(include.clone())
+                .or_default()
+                .push(path.clone());
+        }
+        self.includes.insert(path.clone(), includes);
+        self.files.insert(path, document);
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.unlink(path);
+        self.files.remove(path);
+    }
+
+    fn unlink(&mut self, path: &Path) {
+        for include in self.includes.remove(path).unwrap_or_default() {
+            if let Some(includers) = self.included_by.get_mut(&include) {
+                includers.retain(|file| file != path);
+                if includers.is_empty() {
+                    self.included_by.remove(&include);
+                }
+            }
+        }
+    }
+
+    /// Read the files included from the case that lie outside its layout, such as
+    /// `0/include/initialConditions`, so that their own includes are known too.
+    pub fn load_includes(&mut self) {
+        let mut pending = self.external_includes();
+        while let Some(path) = pending.pop() {
+            if self.files.contains_key(&path) || !Self::is_indexable(&path) {
+                continue;
+            }
+            if let Some(document) = Self::load_included(&path) {
+                self.insert(path.clone(), document);
+                pending.extend(self.includes(&path));
+            }
+        }
+    }
+
+    /// Re-read `path` after it changed on disk, dropping it if it is gone or no longer worth
+    /// indexing. Files of time directories that have not been read are left alone.
+    pub fn reload(&mut self, path: &Path) {
+        let document = if !Self::is_indexable(path) {
+            None
+        } else if self.is_included(path) {
+            Self::load_included(path)
+        } else if self.is_case_file(path) && self.unread_time_dir(path).is_none() {
+            Self::load(path)
+        } else {
+            None
+        };
+        match document {
+            Some(document) => self.insert(path.to_path_buf(), document),
+            None => self.remove(path),
+        }
+    }
+
+    /// The files pulled in by `#include` and `#includeIfPresent` directives in `file`.
+    pub fn includes(&self, file: &Path) -> Vec<PathBuf> {
+        self.includes.get(file).cloned().unwrap_or_default()
+    }
+
+    /// The file `#include "name"` in `file` refers to.
+    pub fn include_path(&self, file: &Path, name: &str) -> PathBuf {
+        resolve_path(&self.root, file.parent().unwrap_or(&self.root), name)
+    }
+
+    pub fn is_included(&self, path: &Path) -> bool {
+        self.included_by.contains_key(path)
+    }
+
+    /// Every file the case includes from outside its own layout, which a watcher on the case
+    /// directories would miss.
+    pub fn external_includes(&self) -> Vec<PathBuf> {
+        let mut includes: Vec<_> = self
+            .included_by
+            .keys()
+            .filter(|include| !self.is_case_file(include))
+            .cloned()
+            .collect();
+        includes.sort();
+        includes.dedup();
+        includes
+    }
+
+    /// The names of the fields in the time directories, e.g. `U`, `p` and `k`.
+    pub fn fields(&self) -> Vec<&str> {
+        let mut fields: Vec<_> = self
+            .files
+            .keys()
+            .filter(|file| self.is_field_file(file))
+            .filter_map(|file| file.file_name()?.to_str())
+            .map(|name| name.strip_suffix(".orig").unwrap_or(name))
+            .collect();
+        fields.sort();
+        fields.dedup();
+        fields
+    }
+
+    /// The files of field `name` in every time directory, such as `0/U` and `0.orig/U.orig`.
+    pub fn field_files(&self, name: &str) -> Vec<&Path> {
+        let mut files: Vec<_> = self
+            .files
+            .keys()
+            .filter(|file| self.is_field_file(file))
+            .filter(|file| {
+                file.file_name()
+                    .and_then(|file_name| file_name.to_str())
+                    .is_some_and(|file_name| {
+                        file_name.strip_suffix(".orig").unwrap_or(file_name) == name
+                    })
+            })
+            .map(PathBuf::as_path)
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// The file of field `name` in the earliest time directory, preferring `0` to `0.orig`.
+    pub fn field_file(&self, name: &str) -> Option<&Path> {
+        let time = |file: &Path| {
+            let dir = file.parent()?.file_name()?.to_str()?;
+            let value = dir
+                .strip_suffix(".orig")
+                .unwrap_or(dir)
+                .parse::<f64>()
+                .ok()?;
+            Some((value, dir.ends_with(".orig")))
+        };
+        self.field_files(name)
+            .into_iter()
+            .filter_map(|file| Some((time(file)?, file)))
+            .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(_, file)| file)
+    }
+
+    /// The solver named by `application` in `system/controlDict`.
+    pub fn application(&self) -> Option<&str> {
+        let control_dict = self.files.get(&self.root.join("system/controlDict"))?;
+        match control_dict.ast.get("application")? {
+            Entry::Value { values, .. } => match values.first()? {
+                Value::Word(word) => Some(&word.text),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn is_field_file(&self, file: &Path) -> bool {
+        file.parent()
+            .and_then(Path::file_name)
+            .is_some_and(|dir| Self::is_time_dir(&dir.to_string_lossy()))
+            && file.parent().and_then(Path::parent) == Some(self.root.as_path())
+    }
+
+    /// The files whose analysis depends on `path`: those including it, directly or through
+    /// other includes, and for the mesh description every field file, as their `boundaryField`
+    /// entries refer to its patches.
+    pub fn dependents(&self, path: &Path) -> Vec<PathBuf> {
+        let mut dependents = Vec::new();
+        let mut pending = vec![path.to_path_buf()];
+        while let Some(changed) = pending.pop() {
+            for file in self.included_by.get(&changed).into_iter().flatten() {
+                if !dependents.contains(file) {
+                    dependents.push(file.clone());
+                    pending.push(file.clone());
+                }
+            }
+        }
+
+        let describes_mesh = [
+            self.root.join("constant/polyMesh/boundary"),
+            self.root.join("system/blockMeshDict"),
+        ]
+        .iter()
+        .any(|mesh| mesh == path);
+        if describes_mesh {
+            let fields = self.files.keys().filter(|file| self.is_field_file(file));
+            dependents.extend(fields.cloned());
+        }
+
+        dependents.sort();
+        dependents.dedup();
+        dependents.retain(|file| file != path);
+        dependents
+    }
+}
+
+#[cfg(test)]
+impl Case {
+    /// A case at `root` holding `files`, given by their path below `root`, text and kind.
+    pub fn with_files(root: &str, files: &[(&str, &str, FileKind)]) -> Self {
+        let mut case = Self::new(PathBuf::from(root));
+        for (file, text, kind) in files {
+            let document = Document::new(text.to_string(), 0, *kind);
+            case.insert(case.root.join(file), document);
+        }
+        case
+    }
+}
+
+/// Resolve a path relative to `dir`, expanding the shorthands `$FOAM_CASE`, `<case>`,
+/// `<system>` and `<constant>` of the case at `root`.
+pub fn resolve_path(root: &Path, dir: &Path, name: &str) -> PathBuf {
+    let prefixes = [
+        ("$FOAM_CASE/", root.to_path_buf()),
+        ("${FOAM_CASE}/", root.to_path_buf()),
+        ("<case>/", root.to_path_buf()),
+        ("<system>/", root.join("system")),
+        ("<constant>/", root.join("constant")),
+    ];
+    let path = prefixes
+        .iter()
+        .find_map(|(prefix, base)| name.strip_prefix(prefix).map(|rest| base.join(rest)))
+        .unwrap_or_else(|| dir.join(name));
+    normalize(&path)
+}
+
+/// Remove `.` and `..` components without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn collect_includes(dict: &Dictionary, names: &mut Vec<String>) {
+    for entry in &dict.entries {
+        match entry {
+            Entry::Directive { name, args, .. }
+                if name.text == "#include" || name.text == "#includeIfPresent" =>
+            {
+                if let Some(Value::String(file) | Value::Word(file)) = args.first() {
+                    names.push(file.text.clone());
+                }
+            }
+            Entry::Dict { dict, .. } => collect_includes(dict, names),
+            Entry::Value { values, .. } | Entry::Bare { values, .. } => {
+                collect_value_includes(values, names)
+            }
+            _ => {}
+        }
+    }
+}
+
+fn collect_value_includes(values: &[Value], names: &mut Vec<String>) {
+    for value in values {
+        match value {
+            Value::Dict(dict) => collect_includes(dict, names),
+            Value::List { items, .. } => collect_value_includes(items, names),
+            _ => {}
+        }
+    }
+}
+
+fn read_dir(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+    paths
+}
+
+fn discover_below(dir: &Path, depth: usize, cases: &mut Vec<PathBuf>) {
+    if Case::is_case_dir(dir) {
+        cases.push(dir.to_path_buf());
+        return;
+    }
+    if depth == 0 {
+        return;
+    }
+    for path in read_dir(dir) {
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if path.is_dir() && !hidden {
+            discover_below(&path, depth - 1, cases);
+        }
+    }
+}
+
+/// Recursively collect files below `dir`, keeping only `boundary` from a `polyMesh` directory.
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let in_mesh = dir.file_name().is_some_and(|name| name == "polyMesh");
+    for path in read_dir(dir) {
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else if !in_mesh || path.file_name().is_some_and(|name| name == "boundary") {
+            files.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_cases_side_by_side() {
+        let workspace =
+            std::env::temp_dir().join(format!("ofoam_ls-discover-{}", std::process::id()));
+        for case in ["cavity", "pitzDaily/steady", ".hidden/cavity"] {
+            let system = workspace.join(case).join("system");
+            std::fs::create_dir_all(&system).unwrap();
+            std::fs::write(system.join("controlDict"), "application icoFoam;\n").unwrap();
+        }
+        std::fs::create_dir_all(workspace.join("cavity/0")).unwrap();
+
+        let cases = Case::discover(&workspace);
+        std::fs::remove_dir_all(&workspace).unwrap();
+
+        assert_eq!(
+            cases,
+            vec![workspace.join("cavity"), workspace.join("pitzDaily/steady")]
+        );
+    }
+
+    #[test]
+    fn test_dependents_follow_includes_and_the_mesh() {
+        let dictionary = FileKind::Dictionary;
+        let case = Case::with_files(
+            "/case",
+            &[
+                (
+                    "0/U",
+                    "#include \"include/initialConditions\"\n",
+                    dictionary,
+                ),
+                (
+                    "0/include/initialConditions",
+                    "#include \"$FOAM_CASE/../common/inlet\"\n",
+                    dictionary,
+                ),
+                ("0/p", "internalField uniform 0;\n", dictionary),
+                (
+                    "system/controlDict",
+                    "application simpleFoam;\n",
+                    dictionary,
+                ),
+            ],
+        );
+        let root = case.root.clone();
+
+        assert_eq!(
+            case.dependents(Path::new("/common/inlet")),
+            vec![root.join("0/U"), root.join("0/include/initialConditions")]
+        );
+        assert_eq!(
+            case.external_includes(),
+            vec![
+                PathBuf::from("/case/0/include/initialConditions"),
+                PathBuf::from("/common/inlet")
+            ]
+        );
+        assert_eq!(
+            case.dependents(&root.join("constant/polyMesh/boundary")),
+            vec![root.join("0/U"), root.join("0/p")]
+        );
+    }
+
+    #[test]
+    fn test_includes_follow_changed_files() {
+        let root = PathBuf::from("/case");
+        let mut case = Case::new(root.clone());
+        let dictionary = |text: &str| Document::new(text.to_string(), 0, FileKind::Dictionary);
+        case.insert(
+            root.join("0/U"),
+            dictionary("#include \"initialConditions\"\n"),
+        );
+        assert!(case.is_included(&root.join("0/initialConditions")));
+
+        case.insert(root.join("0/U"), dictionary("#include \"inlet\"\n"));
+        assert!(!case.is_included(&root.join("0/initialConditions")));
+        assert_eq!(
+            case.dependents(&root.join("0/inlet")),
+            vec![root.join("0/U")]
+        );
+
+        case.remove(&root.join("0/U"));
+        assert!(!case.is_included(&root.join("0/inlet")));
+    }
+
+    #[test]
+    fn test_time_dirs() {
+        assert!(Case::is_time_dir("0"));
+        assert!(Case::is_time_dir("0.005"));
+        assert!(Case::is_time_dir("0.orig"));
+        assert!(Case::is_time_dir("1e-05"));
+        assert!(!Case::is_time_dir("constant"));
+        assert!(!Case::is_time_dir("inf"));
+        assert!(!Case::is_time_dir("NaN"));
+        assert!(!Case::is_time_dir("infinity.orig"));
+        assert!(!Case::is_time_dir("1e999"));
+    }
+
+    #[test]
+    fn test_only_the_initial_time_dirs_are_indexed() {
+        let root = std::env::temp_dir().join(format!("ofoam_ls-times-{}", std::process::id()));
+        for dir in ["0.orig", "0.5", "1", "1e-05", "constant"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+
+        let dirs = Case::initial_time_dirs(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(dirs, vec![root.join("0.orig"), root.join("1e-05")]);
+
+        let mut case = Case::new(root.clone());
+        case.time_dirs = dirs;
+        assert_eq!(case.unread_time_dir(&root.join("1e-05/U")), None);
+        assert_eq!(
+            case.unread_time_dir(&root.join("1/U")),
+            Some(root.join("1"))
+        );
+        assert_eq!(case.unread_time_dir(&root.join("constant/g")), None);
+    }
+}
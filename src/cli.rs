@@ -0,0 +1,139 @@
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tower_lsp::{ClientSocket, LanguageServer, LspService, Server};
+
+/// Command line arguments accepted by the language server.
+#[derive(Debug, Parser)]
+#[command(
+    name = "ofoam_ls",
+    version,
+    about = "Language server for OpenFOAM case files"
+)]
+pub struct Args {
+    /// Communicate over stdin/stdout (the default)
+    #[arg(long, conflicts_with_all = ["listen", "pipe"])]
+    pub stdio: bool,
+
+    /// Listen on 127.0.0.1:<PORT> and serve the first client that connects
+    #[arg(long, value_name = "PORT", conflicts_with = "pipe")]
+    pub listen: Option<u16>,
+
+    /// Connect to the named pipe (unix domain socket on unix) created by the client
+    #[arg(long, value_name = "PATH")]
+    pub pipe: Option<PathBuf>,
+
+    /// Write logs to this file instead of stderr
+    #[arg(long, value_name = "PATH", env = "OFOAM_LS_LOG_FILE")]
+    pub log_file: Option<PathBuf>,
+
+    /// Process id of the client, passed by vscode-languageclient
+    #[arg(long = "clientProcessId", hide = true)]
+    pub client_process_id: Option<u32>,
+}
+
+/// How the server talks to the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transport {
+    Stdio,
+    Listen(u16),
+    Pipe(PathBuf),
+}
+
+impl Args {
+    pub fn transport(&self) -> Transport {
+        if let Some(port) = self.listen {
+            Transport::Listen(port)
+        } else if let Some(path) = &self.pipe {
+            Transport::Pipe(path.clone())
+        } else {
+            Transport::Stdio
+        }
+    }
+}
+
+impl Transport {
+    /// Run the language server over this transport until the client disconnects.
+    pub async fn serve<S: LanguageServer>(
+        self,
+        service: LspService<S>,
+        socket: ClientSocket,
+    ) -> std::io::Result<()> {
+        match self {
+            Transport::Stdio => {
+                serve_on(tokio::io::stdin(), tokio::io::stdout(), service, socket).await;
+            }
+            Transport::Listen(port) => {
+                let listener = tokio::net::TcpListener::bind(("127.0.0.1", port)).await?;
+                tracing::info!(addr = %listener.local_addr()?, "listening");
+
+                let (stream, peer) = listener.accept().await?;
+                tracing::info!(%peer, "client connected");
+
+                let (read, write) = tokio::io::split(stream);
+                serve_on(read, write, service, socket).await;
+            }
+            Transport::Pipe(path) => {
+                let stream = connect_pipe(&path).await?;
+                tracing::info!(path = %path.display(), "connected to pipe");
+
+                let (read, write) = tokio::io::split(stream);
+                serve_on(read, write, service, socket).await;
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn serve_on<I, O, S>(read: I, write: O, service: LspService<S>, socket: ClientSocket)
+where
+    I: AsyncRead + Unpin,
+    O: AsyncWrite,
+    S: LanguageServer,
+{
+    Server::new(read, write, socket).serve(service).await;
+}
+
+#[cfg(unix)]
+async fn connect_pipe(path: &Path) -> std::io::Result<tokio::net::UnixStream> {
+    tokio::net::UnixStream::connect(path).await
+}
+
+#[cfg(windows)]
+async fn connect_pipe(
+    path: &Path,
+) -> std::io::Result<tokio::net::windows::named_pipe::NamedPipeClient> {
+    tokio::net::windows::named_pipe::ClientOptions::new().open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_transport_is_stdio() {
+        let args = Args::parse_from(["ofoam_ls"]);
+        assert_eq!(args.transport(), Transport::Stdio);
+    }
+
+    #[test]
+    fn test_listen_transport() {
+        let args = Args::parse_from(["ofoam_ls", "--listen", "9257"]);
+        assert_eq!(args.transport(), Transport::Listen(9257));
+    }
+
+    #[test]
+    fn test_pipe_transport_accepts_equals_syntax() {
+        let args = Args::parse_from(["ofoam_ls", "--pipe=/tmp/ofoam.sock", "--clientProcessId=12"]);
+        assert_eq!(
+            args.transport(),
+            Transport::Pipe(PathBuf::from("/tmp/ofoam.sock"))
+        );
+    }
+
+    #[test]
+    fn test_transports_are_exclusive() {
+        assert!(Args::try_parse_from(["ofoam_ls", "--stdio", "--listen", "1"]).is_err());
+    }
+}
@@ -0,0 +1,792 @@
+use tower_lsp::lsp_types::{
+    Command, CompletionItem, CompletionItemKind, CompletionTextEdit, Documentation,
+    InsertTextFormat, InsertTextMode, MarkupContent, MarkupKind, Position, Range, TextEdit,
+};
+
+use crate::ast::{self, Dictionary, Entry, Value};
+use crate::case::Case;
+use crate::document::Document;
+use crate::file_kind::{self, FieldClass, FileKind};
+use crate::formatting::FormatOptions;
+use crate::parser::Span;
+use crate::schema::{self, BoundaryCondition, Key, KeyValue, Placeholder};
+use crate::schemes;
+
+/// Completions at `position`. `snippets` says whether the client accepts snippet syntax,
+/// `case` is the case the document belongs to, if any, and `options` the formatting settings
+/// of its project, which inserted keys are padded and nested lines indented by.
+pub fn complete(
+    document: &Document,
+    position: Position,
+    snippets: bool,
+    case: Option<&Case>,
+    options: &FormatOptions,
+) -> Vec<CompletionItem> {
+    let offset = document.offset_at(position);
+    let layout = Layout::at(document, offset, options);
+    let column = layout.value_column;
+    let context = Context::at(document, offset);
+    if !context.is_key_position() {
+        return values(document, &context, snippets, &layout);
+    }
+
+    let present = context.present_keys();
+    let mut items: Vec<_> = schema_keys(document, &context)
+        .into_iter()
+        .filter(|key| !present.contains(&key.name))
+        .map(|key| key_item(key.name, key, snippets, &layout))
+        .collect();
+
+    if let (Some(template), Some(case)) = (schema::field_key(document.kind, &context.path), case) {
+        let mut fields: Vec<_> = case
+            .fields()
+            .into_iter()
+            .filter(|field| file_kind::is_solved(field))
+            .map(str::to_string)
+            .collect();
+        let transient = ["PIMPLE", "PISO"]
+            .iter()
+            .any(|algorithm| document.ast.get(algorithm).is_some());
+        if context.path == ["solvers"] && transient {
+            let finals: Vec<_> = fields.iter().map(|field| format!("{field}Final")).collect();
+            fields.extend(finals);
+        }
+        items.extend(
+            fields
+                .iter()
+                .filter(|field| !present.contains(&field.as_str()))
+                .map(|field| key_item(field, template, snippets, &layout)),
+        );
+    }
+
+    if let (FileKind::FvSchemes, [family], Some(case)) =
+        (document.kind, context.path.as_slice(), case)
+    {
+        let terms = schemes::terms(family, &case.fields(), case.application());
+        items.extend(
+            terms
+                .iter()
+                .filter(|term| !present.contains(&term.as_str()))
+                .map(|term| term_item(term, *family == "fluxRequired", snippets, column)),
+        );
+    }
+    items
+}
+
+/// Completions for the value of the entry under the cursor.
+fn values(
+    document: &Document,
+    context: &Context,
+    snippets: bool,
+    layout: &Layout,
+) -> Vec<CompletionItem> {
+    let Some(key) = context.entry.and_then(Entry::key) else {
+        return Vec::new();
+    };
+
+    match (document.kind, context.path.as_slice(), key.text.as_str()) {
+        (FileKind::Field(_), ["boundaryField", _], "type") => {
+            boundary_types(document, context, false, snippets, layout)
+        }
+        (FileKind::BlockMeshDict | FileKind::Boundary, [_], "type") => {
+            boundary_types(document, context, true, snippets, layout)
+        }
+        (FileKind::FvSchemes, [family], term) if family.ends_with("Schemes") => {
+            scheme_words(document, context, family, term)
+        }
+        (_, _, name) => {
+            let choices = schema_keys(document, context)
+                .into_iter()
+                .find(|key| key.name == name)
+                .and_then(|key| match key.value {
+                    KeyValue::Choice(choices) => Some(choices),
+                    _ => None,
+                })
+                .unwrap_or_default();
+            let range = context.value_range(document, false);
+            choices
+                .iter()
+                .map(|choice| value_item(choice, None, range, false))
+                .collect()
+        }
+    }
+}
+
+/// The keys the schema has for the dictionary under the cursor, including those particular
+/// to the linear solver chosen in it.
+fn schema_keys(document: &Document, context: &Context) -> Vec<&'static Key> {
+    let mut keys: Vec<_> = schema::keys(document.kind, &context.path).iter().collect();
+    if let (FileKind::FvSolution, ["solvers", _]) = (document.kind, context.path.as_slice())
+        && let Some(solver) = context.solver(document)
+    {
+        keys.extend(schema::solver_keys(solver));
+    }
+    keys
+}
+
+fn boundary_types(
+    document: &Document,
+    context: &Context,
+    mesh: bool,
+    snippets: bool,
+    layout: &Layout,
+) -> Vec<CompletionItem> {
+    let class = match document.kind {
+        FileKind::Field(class) => class,
+        _ => None,
+    };
+    let field = field_name(&document.ast);
+    let range = context.value_range(document, true);
+    let present = context.present_keys();
+
+    schema::BOUNDARY_CONDITIONS
+        .iter()
+        .filter(|condition| condition.applies_to(class, field, mesh))
+        .map(|condition| boundary_item(condition, class, &present, range, snippets, layout))
+        .collect()
+}
+
+/// The words that can come next in the scheme of `term`, given those before the cursor.
+fn scheme_words(
+    document: &Document,
+    context: &Context,
+    family: &str,
+    term: &str,
+) -> Vec<CompletionItem> {
+    let words = context.words_before(document);
+    let range = context.value_range(document, false);
+    schemes::next_words(family, term, &words)
+        .into_iter()
+        .map(|suggestion| {
+            let mut next = words.clone();
+            next.push(&suggestion.text);
+            let more = !schemes::next_words(family, term, &next).is_empty();
+            value_item(&suggestion.text, Some(suggestion.doc), range, more)
+        })
+        .collect()
+}
+
+/// The field a field file holds, from the `object` entry of its header.
+fn field_name(ast: &Dictionary) -> Option<&str> {
+    let Some(Entry::Dict { dict, .. }) = ast.get("FoamFile") else {
+        return None;
+    };
+    match dict.get("object")? {
+        Entry::Value { values, .. } => match values.first()? {
+            Value::Word(word) | Value::String(word) => Some(&word.text),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Where the cursor is, as far as completion and signature help are concerned.
+pub struct Context<'a> {
+    pub offset: usize,
+    /// Keys of the enclosing dictionaries
+    pub path: Vec<&'a str>,
+    /// The innermost enclosing dictionary
+    pub scope: &'a Dictionary,
+    /// The entry under the cursor, if any
+    pub entry: Option<&'a Entry>,
+}
+
+impl<'a> Context<'a> {
+    pub fn at(document: &'a Document, offset: usize) -> Self {
+        let path = document.ast.path_at(offset);
+        let scope = document
+            .ast
+            .scopes_at(offset)
+            .pop()
+            .unwrap_or(&document.ast);
+        // An entry still being typed has no `;` yet and ends before the cursor
+        let entry = scope
+            .entries
+            .iter()
+            .find(|entry| ast::contains(entry.span(), offset))
+            .or_else(|| {
+                scope.entries.iter().rev().find(|entry| {
+                    let span = entry.span();
+                    span.end <= offset
+                        && !document.text[span.start..span.end].ends_with([';', '}'])
+                        && document.text[span.end..offset]
+                            .chars()
+                            .all(|c| c == ' ' || c == '\t')
+                })
+            });
+        Self {
+            offset,
+            path,
+            scope,
+            entry,
+        }
+    }
+
+    /// Whether a key goes here: on a blank line of a dictionary, or on a key being typed.
+    pub fn is_key_position(&self) -> bool {
+        match self.entry {
+            None => true,
+            Some(entry) => entry
+                .key()
+                .is_some_and(|key| ast::contains(key.span, self.offset)),
+        }
+    }
+
+    /// Keys already in the enclosing dictionary, apart from the one being typed.
+    fn present_keys(&self) -> Vec<&'a str> {
+        self.scope
+            .entries
+            .iter()
+            .filter(|entry| {
+                !self
+                    .entry
+                    .is_some_and(|current| std::ptr::eq(current, *entry))
+            })
+            .filter_map(|entry| entry.key())
+            .map(|key| key.text.as_str())
+            .collect()
+    }
+
+    /// The linear solver chosen in the enclosing dictionary, directly or in a dictionary it
+    /// merges with `$p;`.
+    fn solver(&self, document: &'a Document) -> Option<&'a str> {
+        let solver = |dict: &'a Dictionary| match dict.get("solver")? {
+            Entry::Value { values, .. } => match values.first()? {
+                Value::Word(word) => Some(word.text.as_str()),
+                _ => None,
+            },
+            _ => None,
+        };
+        solver(self.scope).or_else(|| {
+            self.scope.entries.iter().find_map(|entry| match entry {
+                Entry::Macro { name, .. } => {
+                    match document.ast.resolve_macro(&name.text, name.span.start)? {
+                        Entry::Dict { dict, .. } => solver(dict),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+        })
+    }
+
+    /// The source text of the values of the current entry that end before the cursor.
+    pub fn words_before(&self, document: &'a Document) -> Vec<&'a str> {
+        let Some(Entry::Value { values, .. }) = self.entry else {
+            return Vec::new();
+        };
+        values
+            .iter()
+            .map(Value::span)
+            .filter(|span| span.end < self.offset)
+            .map(|span| &document.text[span.start..span.end])
+            .collect()
+    }
+
+    /// The value word under the cursor, which a completion replaces, with the `;` right after
+    /// it when `semicolon` is set.
+    fn value_range(&self, document: &Document, semicolon: bool) -> Range {
+        let word = match self.entry {
+            Some(Entry::Value { values, .. }) => values.iter().find_map(|value| match value {
+                Value::Word(word) if ast::contains(word.span, self.offset) => Some(word.span),
+                _ => None,
+            }),
+            _ => None,
+        };
+        let Span { start, mut end } = word.unwrap_or(Span {
+            start: self.offset,
+            end: self.offset,
+        });
+        if semicolon && document.text[end..].starts_with(';') {
+            end += 1;
+        }
+        Range::new(document.position_at(start), document.position_at(end))
+    }
+}
+
+/// A completion for the key `name`, described by `key`, which for keys named after fields is
+/// a template.
+fn key_item(name: &str, key: &Key, snippets: bool, layout: &Layout) -> CompletionItem {
+    let padded = pad(name, layout.value_column);
+    let Layout { margin, indent, .. } = layout;
+    let (detail, default, insert_text) = match &key.value {
+        KeyValue::Value(value) => (
+            format!("default: {value}"),
+            Some(*value),
+            if snippets {
+                format!("{padded}${{1:{}}};", escape(value))
+            } else {
+                format!("{padded}{value};")
+            },
+        ),
+        KeyValue::Choice(choices) => (
+            choices.join(" | "),
+            choices.first().copied(),
+            if snippets {
+                let choices: Vec<_> = choices
+                    .iter()
+                    .map(|choice| escape(choice).replace(',', "\\,").replace('|', "\\|"))
+                    .collect();
+                format!("{padded}${{1|{}|}};", choices.join(","))
+            } else {
+                format!("{padded}{};", choices.first().unwrap_or(&""))
+            },
+        ),
+        KeyValue::Dict => (
+            "{ … }".to_string(),
+            None,
+            if snippets {
+                format!(
+                    "{}\n{margin}{{\n{margin}{indent}$0\n{margin}}}",
+                    escape(name)
+                )
+            } else {
+                format!("{name}\n{margin}{{\n{margin}}}")
+            },
+        ),
+        KeyValue::List => (
+            "( … )".to_string(),
+            None,
+            if snippets {
+                format!(
+                    "{}\n{margin}(\n{margin}{indent}$0\n{margin});",
+                    escape(name)
+                )
+            } else {
+                format!("{name}\n{margin}(\n{margin});")
+            },
+        ),
+    };
+
+    let mut documentation = key.doc.to_string();
+    if let Some(default) = default {
+        documentation.push_str(&format!("\n\nDefault: `{default}`"));
+    }
+
+    CompletionItem {
+        label: name.to_string(),
+        kind: Some(match key.value {
+            KeyValue::Dict | KeyValue::List => CompletionItemKind::MODULE,
+            _ => CompletionItemKind::PROPERTY,
+        }),
+        detail: Some(detail),
+        documentation: Some(Documentation::MarkupContent(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: documentation,
+        })),
+        insert_text: Some(insert_text),
+        insert_text_mode: Some(InsertTextMode::AS_IS),
+        insert_text_format: Some(if snippets {
+            InsertTextFormat::SNIPPET
+        } else {
+            InsertTextFormat::PLAIN_TEXT
+        }),
+        ..CompletionItem::default()
+    }
+}
+
+/// A key for the scheme of a term the case needs, e.g. `div(phi,U)`. The keys of
+/// `fluxRequired` take no value.
+fn term_item(term: &str, bare: bool, snippets: bool, column: usize) -> CompletionItem {
+    let padded = pad(term, column);
+    let insert_text = match (bare, snippets) {
+        (true, _) => format!("{term};"),
+        (false, true) => format!("{}$0;", escape(&padded)),
+        (false, false) => padded,
+    };
+    CompletionItem {
+        label: term.to_string(),
+        kind: Some(CompletionItemKind::PROPERTY),
+        detail: Some("used by the solver".to_string()),
+        insert_text: Some(insert_text),
+        insert_text_format: Some(if snippets {
+            InsertTextFormat::SNIPPET
+        } else {
+            InsertTextFormat::PLAIN_TEXT
+        }),
+        command: (!bare).then(trigger_suggest),
+        ..CompletionItem::default()
+    }
+}
+
+/// A plain value word. When `more` words follow it, a space is added and completion opens
+/// again for them.
+fn value_item(text: &str, doc: Option<&str>, range: Range, more: bool) -> CompletionItem {
+    let new_text = if more {
+        format!("{text} ")
+    } else {
+        text.to_string()
+    };
+    CompletionItem {
+        label: text.to_string(),
+        kind: Some(if text.parse::<f64>().is_ok() || text.contains('(') {
+            CompletionItemKind::VALUE
+        } else {
+            CompletionItemKind::ENUM_MEMBER
+        }),
+        documentation: doc.map(|doc| {
+            Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: doc.to_string(),
+            })
+        }),
+        text_edit: Some(CompletionTextEdit::Edit(TextEdit { range, new_text })),
+        command: more.then(trigger_suggest),
+        ..CompletionItem::default()
+    }
+}
+
+/// Ask the editor to open completion again, for the next word of a value.
+fn trigger_suggest() -> Command {
+    Command {
+        title: "Suggest".to_string(),
+        command: "editor.action.triggerSuggest".to_string(),
+        arguments: None,
+    }
+}
+
+fn boundary_item(
+    condition: &BoundaryCondition,
+    class: Option<FieldClass>,
+    present: &[&str],
+    range: Range,
+    snippets: bool,
+    layout: &Layout,
+) -> CompletionItem {
+    let parameters: Vec<_> = condition
+        .parameters
+        .iter()
+        .filter(|(name, _)| !present.contains(name))
+        .collect();
+
+    let mut new_text = format!("{};", condition.name);
+    for (number, (name, placeholder)) in parameters.iter().enumerate() {
+        let value = placeholder_text(*placeholder, class);
+        let padded = pad(name, layout.value_column);
+        let margin = &layout.margin;
+        if snippets {
+            new_text.push_str(&format!(
+                "\n{margin}{padded}${{{}:{}}};",
+                number + 1,
+                escape(&value)
+            ));
+        } else {
+            new_text.push_str(&format!("\n{margin}{padded}{value};"));
+        }
+    }
+
+    let detail = if condition.parameters.is_empty() {
+        None
+    } else {
+        let names: Vec<_> = condition.parameters.iter().map(|(name, _)| *name).collect();
+        Some(names.join(", "))
+    };
+
+    CompletionItem {
+        label: condition.name.to_string(),
+        kind: Some(CompletionItemKind::CLASS),
+        detail,
+        documentation: Some(Documentation::MarkupContent(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: condition.doc.to_string(),
+        })),
+        text_edit: Some(CompletionTextEdit::Edit(TextEdit { range, new_text })),
+        insert_text_mode: Some(InsertTextMode::AS_IS),
+        insert_text_format: Some(if snippets {
+            InsertTextFormat::SNIPPET
+        } else {
+            InsertTextFormat::PLAIN_TEXT
+        }),
+        ..CompletionItem::default()
+    }
+}
+
+/// A value of the right rank for a boundary condition parameter. Fields of unknown class are
+/// taken to be scalars.
+fn placeholder_text(placeholder: Placeholder, class: Option<FieldClass>) -> String {
+    let zero = match class {
+        Some(FieldClass::Vector) => "(0 0 0)",
+        Some(FieldClass::SymmTensor) => "(0 0 0 0 0 0)",
+        Some(FieldClass::Tensor) => "(0 0 0 0 0 0 0 0 0)",
+        Some(FieldClass::Scalar | FieldClass::SphericalTensor) | None => "0",
+    };
+    match placeholder {
+        Placeholder::Field => format!("uniform {zero}"),
+        Placeholder::ScalarField => "uniform 0".to_string(),
+        Placeholder::Function1 => format!("constant {zero}"),
+        Placeholder::Text(text) => text.to_string(),
+    }
+}
+
+/// How inserted text is laid out: the lines it adds below the cursor's line start with that
+/// line's indentation, and nested ones with one more level.
+struct Layout {
+    /// Column values are padded to
+    value_column: usize,
+    /// Whitespace the cursor's line starts with
+    margin: String,
+    /// One level of nesting
+    indent: String,
+}
+
+impl Layout {
+    fn at(document: &Document, offset: usize, options: &FormatOptions) -> Self {
+        let line_start = document.text[..offset].rfind('\n').map_or(0, |end| end + 1);
+        let margin = document.text[line_start..]
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect();
+        Self {
+            value_column: options.value_column,
+            margin,
+            indent: " ".repeat(options.indent),
+        }
+    }
+}
+
+/// `key` padded so that its value starts at `column`, as the formatter lines values up, or
+/// after a single space when the key is too long.
+fn pad(key: &str, column: usize) -> String {
+    format!("{key:<width$}", width = column.max(key.chars().count() + 1))
+}
+
+/// Escape the characters with a meaning in snippet syntax.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('$', "\\$")
+        .replace('}', "\\}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_kind::FileKind;
+
+    fn labels(text: &str, kind: FileKind) -> Vec<String> {
+        let cursor = text.find('|').unwrap();
+        let text = text.replace('|', "");
+        let document = Document::new(text, 0, kind);
+        let position = document.position_at(cursor);
+        complete(&document, position, true, None, &FormatOptions::default())
+            .into_iter()
+            .map(|item| item.label)
+            .collect()
+    }
+
+    #[test]
+    fn test_keys_depend_on_the_dictionary_and_skip_present_ones() {
+        let top = labels(
+            "application icoFoam;\nendTime 1;\nstart|\n",
+            FileKind::ControlDict,
+        );
+        assert!(top.contains(&"startFrom".to_string()));
+        assert!(!top.contains(&"application".to_string()));
+        assert!(!top.contains(&"endTime".to_string()));
+
+        let solver = labels(
+            "solvers\n{\n    p\n    {\n        tolerance 1e-06;\n        |\n    }\n}\n",
+            FileKind::FvSolution,
+        );
+        assert!(solver.contains(&"solver".to_string()));
+        assert!(solver.contains(&"relTol".to_string()));
+        assert!(!solver.contains(&"tolerance".to_string()));
+    }
+
+    #[test]
+    fn test_values_offer_choices_not_keys() {
+        assert_eq!(
+            labels("startFrom |;\n", FileKind::ControlDict),
+            ["startTime", "firstTime", "latestTime"]
+        );
+        assert!(labels("application |;\n", FileKind::ControlDict).is_empty());
+    }
+
+    #[test]
+    fn test_scheme_words_follow_the_typed_ones() {
+        let text = "divSchemes\n{\n    div(phi,U)      bounded Gauss linearUpwind |;\n}\n";
+        assert_eq!(labels(text, FileKind::FvSchemes), ["grad(U)"]);
+
+        let text = "laplacianSchemes\n{\n    default         Gauss linear lim|\n}\n";
+        assert!(labels(text, FileKind::FvSchemes).contains(&"limited".to_string()));
+    }
+
+    #[test]
+    fn test_scheme_terms_come_from_the_case() {
+        let field = FileKind::Field(None);
+        let case = Case::with_files(
+            "/case",
+            &[
+                ("0/U", "", field),
+                ("0/k", "", field),
+                ("0/nut", "", field),
+                ("0/p", "", field),
+            ],
+        );
+        let text = "divSchemes\n{\n    div(phi,U) Gauss linear;\n    \n}\n";
+        let document = Document::new(text.to_string(), 0, FileKind::FvSchemes);
+        let items = complete(
+            &document,
+            Position::new(3, 4),
+            true,
+            Some(&case),
+            &FormatOptions::default(),
+        );
+        let terms: Vec<_> = items.iter().map(|item| item.label.as_str()).collect();
+
+        assert_eq!(
+            terms,
+            ["default", "div(phi,k)", "div((nuEff*dev2(T(grad(U)))))"]
+        );
+    }
+
+    #[test]
+    fn test_solver_keys_follow_the_solver() {
+        let gamg = labels(
+            "solvers\n{\n    p\n    {\n        solver GAMG;\n        |\n    }\n}\n",
+            FileKind::FvSolution,
+        );
+        assert!(gamg.contains(&"nCellsInCoarsestLevel".to_string()));
+        assert!(gamg.contains(&"agglomerator".to_string()));
+        assert!(!gamg.contains(&"nSweeps".to_string()));
+
+        // The solver of a `Final` dictionary comes from the one it merges
+        let smoothers = labels(
+            "solvers\n{\n    p { solver GAMG; }\n    pFinal\n    {\n        $p;\n        smoother |;\n    }\n}\n",
+            FileKind::FvSolution,
+        );
+        assert!(smoothers.contains(&"GaussSeidel".to_string()));
+
+        let text = "solvers { U { solver smoothSolver; | } }\n";
+        assert!(labels(text, FileKind::FvSolution).contains(&"nSweeps".to_string()));
+    }
+
+    #[test]
+    fn test_field_keys_come_from_the_case() {
+        let field = FileKind::Field(None);
+        let case = Case::with_files(
+            "/case",
+            &[("0/U", "", field), ("0/nut", "", field), ("0/p", "", field)],
+        );
+        let complete_at = |text: &str| {
+            let cursor = text.find('|').unwrap();
+            let document = Document::new(text.replace('|', ""), 0, FileKind::FvSolution);
+            let position = document.position_at(cursor);
+            complete(
+                &document,
+                position,
+                true,
+                Some(&case),
+                &FormatOptions::default(),
+            )
+            .into_iter()
+            .map(|item| item.label)
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            complete_at("solvers\n{\n    p { solver PCG; }\n    |\n}\nPIMPLE {}\n"),
+            ["U", "UFinal", "pFinal"]
+        );
+        assert_eq!(
+            complete_at("SIMPLE\n{\n    residualControl\n    {\n        |\n    }\n}\n"),
+            ["U", "p"]
+        );
+        assert_eq!(
+            complete_at("PIMPLE { outerCorrectorResidualControl { p { | } } }\n"),
+            ["tolerance", "relTol"]
+        );
+    }
+
+    #[test]
+    fn test_boundary_types_fit_the_field() {
+        let text = "FoamFile { class volScalarField; object k; }\nboundaryField\n{\n    inlet\n    {\n        type |\n    }\n}\n";
+        let types = labels(text, FileKind::Field(Some(FieldClass::Scalar)));
+        assert!(types.contains(&"fixedValue".to_string()));
+        assert!(types.contains(&"inletOutlet".to_string()));
+        assert!(types.contains(&"kqRWallFunction".to_string()));
+        assert!(types.contains(&"empty".to_string()));
+        assert!(!types.contains(&"nutkWallFunction".to_string()));
+        assert!(!types.contains(&"noSlip".to_string()));
+        assert!(!types.contains(&"wall".to_string()));
+
+        let types = labels(
+            "boundary\n(\n    walls\n    {\n        type w|;\n    }\n);\n",
+            FileKind::BlockMeshDict,
+        );
+        assert!(types.contains(&"wall".to_string()));
+        assert!(!types.contains(&"fixedValue".to_string()));
+    }
+
+    #[test]
+    fn test_boundary_snippets_insert_parameters() {
+        let text = "boundaryField\n{\n    outlet\n    {\n        type inlet;\n    }\n}\n";
+        let document = Document::new(
+            text.to_string(),
+            0,
+            FileKind::Field(Some(FieldClass::Vector)),
+        );
+        let position = document.position_at(text.find("inlet;").unwrap() + 2);
+        let items = complete(&document, position, true, None, &FormatOptions::default());
+        let inlet_outlet = items
+            .iter()
+            .find(|item| item.label == "inletOutlet")
+            .unwrap();
+
+        let Some(CompletionTextEdit::Edit(edit)) = &inlet_outlet.text_edit else {
+            panic!("no text edit");
+        };
+        assert_eq!(
+            edit.new_text,
+            "inletOutlet;\n        inletValue      ${1:uniform (0 0 0)};\n        value           ${2:uniform (0 0 0)};"
+        );
+        assert_eq!(
+            edit.range,
+            Range::new(Position::new(4, 13), Position::new(4, 19))
+        );
+    }
+
+    #[test]
+    fn test_snippets_line_values_up() {
+        let document = Document::new(String::new(), 0, FileKind::ControlDict);
+        let items = complete(
+            &document,
+            Position::new(0, 0),
+            true,
+            None,
+            &FormatOptions::default(),
+        );
+        let start_from = items.iter().find(|item| item.label == "startFrom").unwrap();
+
+        assert_eq!(
+            start_from.insert_text.as_deref(),
+            Some("startFrom       ${1|startTime,firstTime,latestTime|};")
+        );
+
+        let options = FormatOptions {
+            value_column: 20,
+            ..FormatOptions::default()
+        };
+        let items = complete(&document, Position::new(0, 0), false, None, &options);
+        let start_from = items.iter().find(|item| item.label == "startFrom").unwrap();
+        assert_eq!(
+            start_from.insert_text.as_deref(),
+            Some("startFrom           startTime;")
+        );
+    }
+
+    #[test]
+    fn test_dictionary_snippets_indent_their_body() {
+        let text = "solvers\n{\n    \n}\n";
+        let document = Document::new(text.to_string(), 0, FileKind::FvSolution);
+        let options = FormatOptions {
+            indent: 2,
+            ..FormatOptions::default()
+        };
+        let case = Case::with_files("/case", &[("0/U", "", FileKind::Field(None))]);
+        let items = complete(&document, Position::new(2, 4), true, Some(&case), &options);
+        let u = items.iter().find(|item| item.label == "U").unwrap();
+
+        assert_eq!(u.insert_text.as_deref(), Some("U\n    {\n      $0\n    }"));
+        assert_eq!(u.insert_text_mode, Some(InsertTextMode::AS_IS));
+    }
+}
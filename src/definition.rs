@@ -0,0 +1,258 @@
+use std::path::{Path, PathBuf};
+
+use tower_lsp::lsp_types::{Location, Position, Range, SymbolKind, Url};
+
+use crate::ast::{Entry, Value, WordAt};
+use crate::case::Case;
+use crate::document::Document;
+use crate::file_kind::FileKind;
+use crate::symbols;
+
+/// Where the word at `position` is defined: the entry a macro expands to, the file an
+/// `#include` reads, the mesh patch a `boundaryField` entry is for or the field file a scheme
+/// or solver key names.
+pub fn definition(
+    document: &Document,
+    path: &Path,
+    position: Position,
+    case: Option<&Case>,
+) -> Vec<Location> {
+    let offset = document.offset_at(position);
+    let Some(at) = document.ast.word_at(offset) else {
+        return Vec::new();
+    };
+
+    if matches!(at.value, Some(Value::Macro(_))) || matches!(at.entry, Entry::Macro { .. }) {
+        return macro_definition(document, path, &at, case)
+            .into_iter()
+            .collect();
+    }
+    if let Entry::Directive { name, .. } = at.entry
+        && matches!(name.text.as_str(), "#include" | "#includeIfPresent")
+        && at.value.is_some()
+    {
+        let include = match case {
+            Some(case) => case.include_path(path, &at.word.text),
+            None => path.with_file_name(&at.word.text),
+        };
+        return include
+            .is_file()
+            .then(|| file_location(&include))
+            .into_iter()
+            .flatten()
+            .collect();
+    }
+    let Some(case) = case else {
+        return Vec::new();
+    };
+
+    let is_key = at.value.is_none() && at.entry.key().is_some();
+    match document.kind {
+        FileKind::Field(_) if is_key && document.ast.path_at(offset) == ["boundaryField"] => {
+            patch_definitions(case, &at.word.text)
+        }
+        FileKind::FvSchemes | FileKind::FvSolution if is_key => {
+            let inner = (offset - at.word.span.start).saturating_sub(usize::from(at.word.quoted));
+            let name = name_at(&at.word.text, inner);
+            let name = match name.strip_suffix("Final") {
+                Some(field) if case.field_file(name).is_none() => field,
+                _ => name,
+            };
+            case.field_file(name)
+                .and_then(file_location)
+                .into_iter()
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The entry a macro refers to, in the document itself or in the files it includes.
+fn macro_definition(
+    document: &Document,
+    path: &Path,
+    at: &WordAt,
+    case: Option<&Case>,
+) -> Option<Location> {
+    let name = &at.word.text;
+    if let Some(entry) = document.ast.resolve_macro(name, at.word.span.start) {
+        return entry_location(document, path, entry);
+    }
+
+    let case = case?;
+    let mut visited: Vec<PathBuf> = Vec::new();
+    let mut pending = case.includes(path);
+    while let Some(include) = pending.pop() {
+        if visited.contains(&include) {
+            continue;
+        }
+        let Some(included) = case.files.get(&include) else {
+            continue;
+        };
+        if let Some(entry) = included.ast.resolve_macro(name, 0) {
+            return entry_location(included, &include, entry);
+        }
+        pending.extend(case.includes(&include));
+        visited.push(include);
+    }
+    None
+}
+
+/// The patches named `name` in `constant/polyMesh/boundary` and `system/blockMeshDict`.
+fn patch_definitions(case: &Case, name: &str) -> Vec<Location> {
+    let mut locations = Vec::new();
+    for file in ["constant/polyMesh/boundary", "system/blockMeshDict"] {
+        let path = case.root.join(file);
+        let Some(document) = case.files.get(&path) else {
+            continue;
+        };
+        let Ok(uri) = Url::from_file_path(&path) else {
+            continue;
+        };
+        locations.extend(
+            symbols::definitions(document)
+                .into_iter()
+                .filter(|definition| {
+                    definition.kind == SymbolKind::INTERFACE && definition.name == name
+                })
+                .map(|definition| Location::new(uri.clone(), document.range_of(definition.span))),
+        );
+    }
+    locations
+}
+
+/// The word around `offset` in a key such as `div(phi,U)` or `"(U|k|epsilon)Final"`.
+fn name_at(text: &str, offset: usize) -> &str {
+    let is_name = |c: char| c.is_alphanumeric() || c == '_' || c == '.';
+    let start = text[..offset.min(text.len())]
+        .rfind(|c| !is_name(c))
+        .map_or(0, |index| index + 1);
+    let end = text[start..]
+        .find(|c| !is_name(c))
+        .map_or(text.len(), |index| start + index);
+    &text[start..end]
+}
+
+fn entry_location(document: &Document, path: &Path, entry: &Entry) -> Option<Location> {
+    let span = entry.key().map_or(entry.span(), |key| key.span);
+    Some(Location::new(
+        Url::from_file_path(path).ok()?,
+        document.range_of(span),
+    ))
+}
+
+fn file_location(path: &Path) -> Option<Location> {
+    Some(Location::new(
+        Url::from_file_path(path).ok()?,
+        Range::default(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_kind::FieldClass;
+
+    fn case() -> Case {
+        Case::with_files(
+            "/case",
+            &[
+                (
+                    "constant/polyMesh/boundary",
+                    "1\n(\n    movingWall\n    {\n        type wall;\n    }\n)\n",
+                    FileKind::Boundary,
+                ),
+                (
+                    "system/blockMeshDict",
+                    "boundary\n(\n    movingWall\n    {\n        type wall;\n    }\n);\n",
+                    FileKind::BlockMeshDict,
+                ),
+                (
+                    "0/include/initialConditions",
+                    "flowVelocity (1 0 0);\n",
+                    FileKind::Dictionary,
+                ),
+                ("0/U", "", FileKind::Field(Some(FieldClass::Vector))),
+                ("0.orig/U", "", FileKind::Field(Some(FieldClass::Vector))),
+            ],
+        )
+    }
+
+    fn targets(text: &str, file: &str, kind: FileKind) -> Vec<(String, u32)> {
+        let cursor = text.find('|').unwrap();
+        targets_at(&text.replace('|', ""), cursor, file, kind)
+    }
+
+    /// For texts where `|` means something, such as keys that are regular expressions.
+    fn targets_at(text: &str, cursor: usize, file: &str, kind: FileKind) -> Vec<(String, u32)> {
+        let mut case = case();
+        let text = text.to_string();
+        let document = Document::new(text.clone(), 0, kind);
+        let path = case.root.join(file);
+        case.insert(path.clone(), Document::new(text, 0, kind));
+        definition(&document, &path, document.position_at(cursor), Some(&case))
+            .into_iter()
+            .map(|location| (location.uri.path().to_string(), location.range.start.line))
+            .collect()
+    }
+
+    #[test]
+    fn test_macros_and_patches_lead_to_their_definitions() {
+        let field = FileKind::Field(Some(FieldClass::Vector));
+        let text =
+            "#include \"include/initialConditions\"\ninternalField uniform $flow|Velocity;\n";
+        assert_eq!(
+            targets(text, "0/U", field),
+            [("/case/0/include/initialConditions".to_string(), 0)]
+        );
+
+        let text = "wallValue 1;\nboundaryField\n{\n    moving|Wall\n    {\n        value $wallValue;\n    }\n}\n";
+        assert_eq!(
+            targets(text, "0/U", field),
+            [
+                ("/case/constant/polyMesh/boundary".to_string(), 2),
+                ("/case/system/blockMeshDict".to_string(), 2)
+            ]
+        );
+        let text = "wallValue 1;\nboundaryField\n{\n    movingWall\n    {\n        value $wall|Value;\n    }\n}\n";
+        assert_eq!(targets(text, "0/U", field), [("/case/0/U".to_string(), 0)]);
+    }
+
+    #[test]
+    fn test_fields_in_keys_lead_to_their_files() {
+        let text = "divSchemes\n{\n    div(phi,|U) Gauss linear;\n}\n";
+        assert_eq!(
+            targets(text, "system/fvSchemes", FileKind::FvSchemes),
+            [("/case/0/U".to_string(), 0)]
+        );
+        let text = "solvers\n{\n    UFin|al { solver PBiCGStab; }\n}\n";
+        assert_eq!(
+            targets(text, "system/fvSolution", FileKind::FvSolution),
+            [("/case/0/U".to_string(), 0)]
+        );
+    }
+
+    #[test]
+    fn test_cursor_on_the_opening_quote_of_a_key() {
+        let text = "solvers\n{\n    \"UFinal\" { solver PBiCGStab; }\n}\n";
+        assert_eq!(
+            targets_at(
+                text,
+                text.find('"').unwrap(),
+                "system/fvSolution",
+                FileKind::FvSolution
+            ),
+            [("/case/0/U".to_string(), 0)]
+        );
+        let text = "solvers\n{\n    \"(U|k)Final\" { solver PBiCGStab; }\n}\n";
+        assert!(
+            targets_at(
+                text,
+                text.find('"').unwrap(),
+                "system/fvSolution",
+                FileKind::FvSolution
+            )
+            .is_empty()
+        );
+    }
+}
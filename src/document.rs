@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+
+use crate::ast::{self, Dictionary, SyntaxError};
+use crate::file_kind::FileKind;
+use crate::guard;
+use crate::parser::{self, Span, Token};
+
+/// Whether the last analysis of a document ran to completion.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Health {
+    Ok,
+    Failed(String),
+}
+
+/// The text of a file together with the result of analysing it.
+#[derive(Debug)]
+pub struct Document {
+    pub text: String,
+    pub version: i32,
+    pub kind: FileKind,
+    line_starts: Vec<usize>,
+    pub tokens: Vec<Token>,
+    pub spans: Vec<Span>,
+    pub errors: HashMap<Span, String>,
+    pub ast: Dictionary,
+    pub syntax_errors: Vec<SyntaxError>,
+    pub health: Health,
+}
+
+impl Document {
+    /// Create and analyse a document.
+    pub fn new(text: String, version: i32, kind: FileKind) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        let mut document = Self {
+            text,
+            version,
+            kind,
+            line_starts,
+            tokens: Vec::new(),
+            spans: Vec::new(),
+            errors: HashMap::new(),
+            ast: Dictionary::default(),
+            syntax_errors: Vec::new(),
+            health: Health::Ok,
+        };
+        document.analyze();
+        document
+    }
+
+    /// (Re-)run the analysis, recording a failure rather than letting a panic escape.
+    pub fn analyze(&mut self) {
+        let text = &self.text;
+        let kind = self.kind;
+        let analysis = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let (_, (tokens, spans)) =
+                parser::scan(text).map_err(|error| format!("could not tokenise: {error}"))?;
+            let mut errors = parser::get_errors(&tokens, &spans);
+            let (ast, syntax_errors) = ast::parse(text, &tokens, &spans);
+            errors.extend(kind.check(&ast));
+            Ok::<_, String>((tokens, spans, errors, ast, syntax_errors))
+        }))
+        .unwrap_or_else(|payload| Err(guard::panic_message(payload.as_ref())));
+
+        match analysis {
+            Ok((tokens, spans, errors, ast, syntax_errors)) => {
+                self.tokens = tokens;
+                self.spans = spans;
+                self.errors = errors;
+                self.ast = ast;
+                self.syntax_errors = syntax_errors;
+                self.health = Health::Ok;
+            }
+            Err(message) => {
+                tracing::warn!(%message, "analysis failed");
+                self.tokens.clear();
+                self.spans.clear();
+                self.errors.clear();
+                self.ast = Dictionary::default();
+                self.syntax_errors.clear();
+                self.health = Health::Failed(message);
+            }
+        }
+    }
+
+    /// Byte offset of an LSP (UTF-16) position, clamped to the text.
+    pub fn offset_at(&self, position: Position) -> usize {
+        let Some(&line_start) = self.line_starts.get(position.line as usize) else {
+            return self.text.len();
+        };
+        let line = self.text[line_start..].lines().next().unwrap_or("");
+
+        let mut units = 0;
+        for (i, c) in line.char_indices() {
+            if units >= position.character as usize {
+                return line_start + i;
+            }
+            units += c.len_utf16();
+        }
+        line_start + line.len()
+    }
+
+    /// LSP (UTF-16) position of a byte offset.
+    pub fn position_at(&self, offset: usize) -> Position {
+        let offset = offset.min(self.text.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line];
+        let character = self.text[line_start..offset].encode_utf16().count();
+        Position::new(line as u32, character as u32)
+    }
+
+    pub fn range_of(&self, span: Span) -> Range {
+        Range::new(self.position_at(span.start), self.position_at(span.end))
+    }
+
+    /// The token under the byte offset, if any.
+    pub fn token_at(&self, offset: usize) -> Option<(Token, Span)> {
+        self.tokens
+            .iter()
+            .zip(self.spans.iter())
+            .find(|(_, span)| span.start <= offset && offset < span.end)
+            .map(|(token, span)| (*token, *span))
+    }
+
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let errors = self.errors.iter().map(|(span, message)| (*span, message));
+        let syntax_errors = self
+            .syntax_errors
+            .iter()
+            .map(|error| (error.span, &error.message));
+
+        let mut diagnostics: Vec<_> = syntax_errors
+            .chain(errors)
+            .map(|(span, message)| Diagnostic {
+                range: self.range_of(span),
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("Foam Language Server".to_string()),
+                message: message.clone(),
+                ..Diagnostic::default()
+            })
+            .collect();
+
+        if let Health::Failed(message) = &self.health {
+            diagnostics.push(guard::health_diagnostic(message));
+        }
+        diagnostics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positions_round_trip_through_utf16_and_crlf() {
+        let document = Document::new("a;\r\n// µ\r\nb;".to_string(), 0, FileKind::Dictionary);
+
+        let b = document.text.find('b').unwrap();
+        assert_eq!(document.position_at(b), Position::new(2, 0));
+        assert_eq!(document.offset_at(Position::new(2, 0)), b);
+
+        let after_mu = document.text.find('µ').unwrap() + 'µ'.len_utf8();
+        assert_eq!(document.position_at(after_mu), Position::new(1, 4));
+        assert_eq!(document.offset_at(Position::new(1, 4)), after_mu);
+    }
+
+    #[test]
+    fn test_uniform_errors_are_reported() {
+        let document = Document::new(
+            "value uniform (0 0 a);\n".to_string(),
+            0,
+            FileKind::Dictionary,
+        );
+
+        assert_eq!(document.health, Health::Ok);
+        assert_eq!(document.diagnostics().len(), 1);
+    }
+
+    #[test]
+    fn test_field_files_need_their_entries() {
+        let document = Document::new(
+            "dimensions [0 1 -1 0 0 0 0];\ninternalField uniform (0 0 0);\n".to_string(),
+            0,
+            FileKind::Field(None),
+        );
+
+        let messages: Vec<_> = document
+            .diagnostics()
+            .into_iter()
+            .map(|d| d.message)
+            .collect();
+        assert_eq!(messages, vec!["Field file is missing boundaryField"]);
+    }
+}
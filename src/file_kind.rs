@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::ast::{Dictionary, Entry};
+use crate::case::Case;
+use crate::parser::Span;
+
+/// Extensions of files that are never OpenFOAM dictionaries, even inside a case.
+const NON_FOAM_EXTENSIONS: &[&str] = &[
+    "md", "rst", "txt", "py", "sh", "c", "C", "H", "cpp", "hpp", "json", "yaml", "yml", "xml",
+    "html", "foam", "stl", "obj", "vtk", "vtp", "gz", "eMesh", "csv", "dat", "xy",
+];
+
+/// Fields calculated from others rather than solved for, such as the turbulent viscosity.
+const DERIVED_FIELDS: &[&str] = &["nut", "nuSgs", "mut", "alphat"];
+
+/// Whether a field has an equation of its own, so needs a linear solver.
+pub fn is_solved(field: &str) -> bool {
+    !DERIVED_FIELDS.contains(&field)
+}
+
+/// The rank of the values held by a field file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldClass {
+    Scalar,
+    Vector,
+    SymmTensor,
+    Tensor,
+    SphericalTensor,
+}
+
+impl FieldClass {
+    /// The class named in a `FoamFile` header, e.g. `volVectorField` or `surfaceScalarField`.
+    fn from_class(class: &str) -> Option<Self> {
+        let rank = ["vol", "surface", "point"]
+            .iter()
+            .find_map(|prefix| class.strip_prefix(prefix))?
+            .strip_suffix("Field")?;
+        match rank {
+            "Scalar" => Some(Self::Scalar),
+            "Vector" => Some(Self::Vector),
+            "SymmTensor" => Some(Self::SymmTensor),
+            "Tensor" => Some(Self::Tensor),
+            "SphericalTensor" => Some(Self::SphericalTensor),
+            _ => None,
+        }
+    }
+
+    /// Guess the class of a field without a header from the usual solver field names.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "U" | "Ub" | "Uf" | "UMean" | "D" | "pointDisplacement" => Some(Self::Vector),
+            "R" | "sigma" | "tau" => Some(Self::SymmTensor),
+            "p" | "p_rgh" | "k" | "epsilon" | "omega" | "nut" | "nuTilda" | "alphat" | "T"
+            | "kl" | "kt" | "v2" | "f" | "ReThetat" | "gammaInt" | "s" => Some(Self::Scalar),
+            _ if name.starts_with("alpha.") => Some(Self::Scalar),
+            _ => None,
+        }
+    }
+}
+
+/// What an OpenFOAM file is, which decides the checks and completions that apply to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    ControlDict,
+    FvSchemes,
+    FvSolution,
+    /// `fvOptions`, `fvConstraints` and `fvModels`
+    FvOptions,
+    BlockMeshDict,
+    SnappyHexMeshDict,
+    DecomposeParDict,
+    SetFieldsDict,
+    TopoSetDict,
+    /// `physicalProperties` or the older `transportProperties`
+    PhysicalProperties,
+    /// `momentumTransport` or the older `turbulenceProperties`
+    MomentumTransport,
+    ThermophysicalProperties,
+    /// The gravity vector `constant/g`
+    Gravity,
+    /// `constant/polyMesh/boundary`
+    Boundary,
+    /// A field in a time directory, with its class when it is known
+    Field(Option<FieldClass>),
+    /// Any other OpenFOAM dictionary
+    Dictionary,
+}
+
+impl FileKind {
+    /// Classify the file at `path` by its `FoamFile` header, its place in a case and its name.
+    /// Returns `None` for files that are not OpenFOAM files at all.
+    pub fn detect(path: &Path, text: &str) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy();
+        let name = name.strip_suffix(".orig").unwrap_or(&name);
+
+        if let Some(header) = header(text) {
+            let class = header.get("class").map(String::as_str);
+            let object = header.get("object").map_or(name, String::as_str);
+            return Some(match class {
+                Some("polyBoundaryMesh") => Self::Boundary,
+                Some(class) if FieldClass::from_class(class).is_some() => {
+                    Self::Field(FieldClass::from_class(class))
+                }
+                _ if object == "g" && Self::from_layout(path, name) == Some(Self::Gravity) => {
+                    Self::Gravity
+                }
+                _ => Self::from_name(object).unwrap_or(Self::Dictionary),
+            });
+        }
+
+        let foreign = path
+            .extension()
+            .is_some_and(|extension| NON_FOAM_EXTENSIONS.iter().any(|other| extension == *other));
+        if foreign {
+            return None;
+        }
+
+        if let Some(kind) = Self::from_layout(path, name) {
+            return Some(kind);
+        }
+        Self::from_name(name)
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "controlDict" => Self::ControlDict,
+            "fvSchemes" => Self::FvSchemes,
+            "fvSolution" => Self::FvSolution,
+            "fvOptions" | "fvConstraints" | "fvModels" => Self::FvOptions,
+            "blockMeshDict" => Self::BlockMeshDict,
+            "snappyHexMeshDict" => Self::SnappyHexMeshDict,
+            "decomposeParDict" => Self::DecomposeParDict,
+            "setFieldsDict" => Self::SetFieldsDict,
+            "topoSetDict" => Self::TopoSetDict,
+            "physicalProperties" | "transportProperties" => Self::PhysicalProperties,
+            "momentumTransport" | "turbulenceProperties" => Self::MomentumTransport,
+            "thermophysicalProperties" => Self::ThermophysicalProperties,
+            _ => return None,
+        })
+    }
+
+    /// Classify a file without a header by where it sits in its case.
+    fn from_layout(path: &Path, name: &str) -> Option<Self> {
+        let root = Case::root_of(path)?;
+        let relative = path.strip_prefix(&root).ok()?;
+        let components: Vec<_> = relative
+            .components()
+            .filter_map(|component| component.as_os_str().to_str())
+            .collect();
+
+        match components.as_slice() {
+            ["constant", "polyMesh", "boundary"] => Some(Self::Boundary),
+            // `g` is too common a name to mean gravity anywhere else
+            ["constant", _] if name == "g" => Some(Self::Gravity),
+            ["system" | "constant", ..] => Some(Self::from_name(name).unwrap_or(Self::Dictionary)),
+            [dir, _] if Case::is_time_dir(dir) => Some(Self::Field(FieldClass::from_name(name))),
+            _ => None,
+        }
+    }
+
+    /// Errors specific to this kind of file, keyed like `parser::get_errors`.
+    pub fn check(&self, ast: &Dictionary) -> HashMap<Span, String> {
+        let mut errors = HashMap::new();
+        if let Self::Field(_) = self {
+            // Entries may come from an included file or a merged macro; only complain when
+            // the file is self-contained
+            let self_contained = !ast
+                .entries
+                .iter()
+                .any(|entry| matches!(entry, Entry::Directive { .. } | Entry::Macro { .. }));
+            let missing: Vec<_> = ["dimensions", "internalField", "boundaryField"]
+                .into_iter()
+                .filter(|required| {
+                    !ast.entries.iter().any(|entry| match entry {
+                        Entry::Dict { key, .. } | Entry::Value { key, .. } => key.text == *required,
+                        _ => false,
+                    })
+                })
+                .collect();
+            // Reported on the header, away from the spans syntax errors are keyed by
+            let header = ast.entries.iter().find_map(|entry| match entry {
+                Entry::Dict { key, .. } if key.text == "FoamFile" => Some(key.span),
+                _ => None,
+            });
+            if self_contained && !missing.is_empty() {
+                errors.insert(
+                    header.unwrap_or_default(),
+                    format!("Field file is missing {}", missing.join(", ")),
+                );
+            }
+        }
+        errors
+    }
+}
+
+/// The entries of the `FoamFile` header, if the text starts with one after any comments.
+fn header(text: &str) -> Option<HashMap<String, String>> {
+    let mut rest = text.trim_start();
+    loop {
+        if let Some(comment) = rest.strip_prefix("/*") {
+            rest = &comment[comment.find("*/")? + 2..];
+        } else if let Some(comment) = rest.strip_prefix("//") {
+            rest = comment.split_once('\n').map_or("", |(_, after)| after);
+        } else {
+            break;
+        }
+        rest = rest.trim_start();
+    }
+
+    let body = rest
+        .strip_prefix("FoamFile")?
+        .trim_start()
+        .strip_prefix('{')?;
+    let body = &body[..body.find('}')?];
+    Some(
+        body.split(';')
+            .filter_map(|entry| {
+                let (key, value) = entry.trim().split_once(char::is_whitespace)?;
+                Some((key.to_string(), value.trim().trim_matches('"').to_string()))
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Document;
+
+    #[test]
+    fn test_detect_by_header() {
+        let text = "/*--------*\\\n| =========                 |\n\\*--------*/\nFoamFile\n{\n    version     2.0;\n    format      ascii;\n    class       volVectorField;\n    object      U;\n}\n";
+        assert_eq!(
+            FileKind::detect(Path::new("/somewhere/initial"), text),
+            Some(FileKind::Field(Some(FieldClass::Vector)))
+        );
+
+        let text = "FoamFile { class dictionary; object fvSchemes; }\n";
+        assert_eq!(
+            FileKind::detect(Path::new("/somewhere/schemes.txt"), text),
+            Some(FileKind::FvSchemes)
+        );
+    }
+
+    #[test]
+    fn test_detect_by_name_and_ignore_other_files() {
+        let path = Path::new("/no/case/system/controlDict");
+        assert_eq!(FileKind::detect(path, ""), Some(FileKind::ControlDict));
+        assert_eq!(
+            FileKind::detect(Path::new("/no/case/README.md"), "# U\n"),
+            None
+        );
+        assert_eq!(
+            FileKind::detect(Path::new("/no/case/solver.C"), "int main;\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_gravity_only_in_constant() {
+        let root = std::env::temp_dir().join(format!("ofoam_ls-gravity-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("system")).unwrap();
+        std::fs::create_dir_all(root.join("constant")).unwrap();
+        std::fs::write(root.join("system/controlDict"), "").unwrap();
+
+        let header = "FoamFile { class uniformDimensionedVectorField; object g; }\n";
+        for text in ["", header] {
+            assert_eq!(
+                FileKind::detect(&root.join("constant/g"), text),
+                Some(FileKind::Gravity)
+            );
+            assert_eq!(
+                FileKind::detect(&root.join("system/g"), text),
+                Some(FileKind::Dictionary)
+            );
+        }
+        assert_eq!(FileKind::detect(Path::new("/no/case/constant/g"), ""), None);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_missing_field_entries_are_reported_on_the_header() {
+        let text = "FoamFile { class volScalarField; object p; }\ndimensions [0 2 -2 0 0 0 0];\n";
+        let document = Document::new(text.to_string(), 0, FileKind::Field(None));
+        assert_eq!(
+            document
+                .errors
+                .get(&Span { start: 0, end: 8 })
+                .map(String::as_str),
+            Some("Field file is missing internalField, boundaryField")
+        );
+    }
+}
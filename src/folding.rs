@@ -0,0 +1,242 @@
+use tower_lsp::lsp_types::{FoldingRange, FoldingRangeKind, Position};
+
+use crate::ast::{Dictionary, Entry, Value};
+use crate::document::Document;
+use crate::parser::{Span, Token};
+
+/// Lists of mesh data, folded away by default along with `nonuniform` field values. The
+/// extension recognises the same keys to collapse them when a file is opened.
+const PAYLOAD_KEYS: &[&str] = &["vertices", "blocks", "edges", "faces", "points"];
+
+/// Folding ranges for dictionaries, multi-line lists and comments. Data payloads are regions
+/// collapsed to their length where it is known.
+pub fn folding_ranges(document: &Document) -> Vec<FoldingRange> {
+    let mut folder = Folder {
+        document,
+        ranges: Vec::new(),
+    };
+    folder.comments();
+    folder.dictionary(&document.ast);
+
+    let mut ranges = folder.ranges;
+    // An entry and the list or dictionary it opens on the same line fold as one
+    ranges.sort_by_key(|range| (range.start_line, std::cmp::Reverse(range.end_line)));
+    ranges.dedup_by_key(|range| range.start_line);
+    ranges
+}
+
+struct Folder<'a> {
+    document: &'a Document,
+    ranges: Vec<FoldingRange>,
+}
+
+impl Folder<'_> {
+    fn line(&self, offset: usize) -> u32 {
+        self.document.position_at(offset).line
+    }
+
+    /// Fold from the line of `start` to the line of `end`. When the closing delimiter at `end`
+    /// begins its line, that line stays visible.
+    fn push(
+        &mut self,
+        start: usize,
+        end: usize,
+        kind: Option<FoldingRangeKind>,
+        collapsed_text: Option<String>,
+    ) {
+        let start_line = self.line(start);
+        let mut end_line = self.line(end);
+        let closing = end.saturating_sub(1);
+        let line_start = self
+            .document
+            .offset_at(Position::new(self.line(closing), 0));
+        if self.document.text[line_start..closing].trim().is_empty() && end_line > start_line {
+            end_line -= 1;
+        }
+        if end_line > start_line {
+            self.ranges.push(FoldingRange {
+                start_line,
+                start_character: None,
+                end_line,
+                end_character: None,
+                kind,
+                collapsed_text,
+            });
+        }
+    }
+
+    /// Block comments, the banner among them, and runs of line comments.
+    fn comments(&mut self) {
+        let mut run: Option<Span> = None;
+        let comments: Vec<_> = self
+            .document
+            .tokens
+            .iter()
+            .zip(&self.document.spans)
+            .filter(|(token, _)| matches!(token, Token::BlockComment | Token::LineComment))
+            .map(|(token, span)| (*token, *span))
+            .collect();
+
+        for (token, span) in comments {
+            if token == Token::BlockComment {
+                self.push_comment(span);
+                continue;
+            }
+            run = match run {
+                Some(previous) if self.line(span.start) == self.line(previous.end) + 1 => {
+                    Some(Span {
+                        start: previous.start,
+                        end: span.end,
+                    })
+                }
+                Some(previous) => {
+                    self.push_comment(previous);
+                    Some(span)
+                }
+                None => Some(span),
+            };
+        }
+        if let Some(run) = run {
+            self.push_comment(run);
+        }
+    }
+
+    fn push_comment(&mut self, span: Span) {
+        let start_line = self.line(span.start);
+        let end_line = self.line(span.end.saturating_sub(1));
+        if end_line > start_line {
+            self.ranges.push(FoldingRange {
+                start_line,
+                start_character: None,
+                end_line,
+                end_character: None,
+                kind: Some(FoldingRangeKind::Comment),
+                collapsed_text: None,
+            });
+        }
+    }
+
+    fn dictionary(&mut self, dict: &Dictionary) {
+        for entry in &dict.entries {
+            match entry {
+                Entry::Dict { dict, span, .. } => {
+                    self.push(span.start, span.end, None, None);
+                    self.dictionary(dict);
+                }
+                Entry::Value { key, values, span } => {
+                    let payload =
+                        PAYLOAD_KEYS.contains(&key.text.as_str()) || is_nonuniform(values);
+                    self.entry_values(span.start, values, payload);
+                }
+                Entry::Bare { values, span } => self.entry_values(span.start, values, false),
+                Entry::Directive { .. } | Entry::Macro { .. } => {}
+            }
+        }
+    }
+
+    /// Fold the first list of an entry from the line of `start`, as a payload showing its
+    /// length or as a plain region, then whatever is nested in the values.
+    fn entry_values(&mut self, start: usize, values: &[Value], payload: bool) {
+        let Some((index, items, span)) =
+            values
+                .iter()
+                .enumerate()
+                .find_map(|(index, value)| match value {
+                    Value::List { items, span } => Some((index, items, span)),
+                    _ => None,
+                })
+        else {
+            self.values(values);
+            return;
+        };
+        if payload {
+            self.push(
+                start,
+                span.end,
+                Some(FoldingRangeKind::Region),
+                collapsed_text(items),
+            );
+        } else {
+            self.push(start, span.end, None, None);
+        }
+        self.values(&values[..index]);
+        self.values(items);
+        self.values(&values[index + 1..]);
+    }
+
+    /// Lists and dictionaries nested in values, such as the patches of `boundary`.
+    fn values(&mut self, values: &[Value]) {
+        let mut previous: Option<&Value> = None;
+        for value in values {
+            match value {
+                Value::List { items, span } => {
+                    self.push(span.start, span.end, None, None);
+                    self.values(items);
+                }
+                Value::Dict(dict) => {
+                    let start = match previous {
+                        Some(Value::Word(name)) => name.span.start,
+                        _ => dict.span.start,
+                    };
+                    self.push(start, dict.span.end, None, None);
+                    self.dictionary(dict);
+                }
+                _ => {}
+            }
+            previous = Some(value);
+        }
+    }
+}
+
+/// Whether the values are a field written out cell by cell, `nonuniform List<scalar> 400 (...)`.
+fn is_nonuniform(values: &[Value]) -> bool {
+    matches!(values.first(), Some(Value::Word(word)) if word.text == "nonuniform")
+}
+
+/// The length of a list of numbers or of tuples, such as points or cell values. Lists mixing
+/// words in, like `blocks`, have no useful length.
+fn collapsed_text(items: &[Value]) -> Option<String> {
+    let uniform = items
+        .iter()
+        .all(|item| matches!(item, Value::Number(..) | Value::List { .. }));
+    let noun = if items.len() == 1 { "item" } else { "items" };
+    uniform.then(|| format!("{} {noun}", items.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_kind::{FieldClass, FileKind};
+
+    fn ranges(text: &str, kind: FileKind) -> Vec<(u32, u32, Option<FoldingRangeKind>)> {
+        let document = Document::new(text.to_string(), 0, kind);
+        folding_ranges(&document)
+            .into_iter()
+            .map(|range| (range.start_line, range.end_line, range.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_fold_dictionaries_comments_and_payloads() {
+        let text = "/*---*\\\n  banner\n\\*---*/\n// one\n// two\ninternalField nonuniform List<scalar>\n3\n(\n1\n2\n3\n)\n;\nboundaryField\n{\n    inlet\n    {\n        type zeroGradient;\n    }\n}\n";
+        assert_eq!(
+            ranges(text, FileKind::Field(Some(FieldClass::Scalar))),
+            [
+                (0, 2, Some(FoldingRangeKind::Comment)),
+                (3, 4, Some(FoldingRangeKind::Comment)),
+                (5, 10, Some(FoldingRangeKind::Region)),
+                (13, 18, None),
+                (15, 17, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fold_patches_in_lists() {
+        let text = "boundary\n(\n    walls\n    {\n        type wall;\n    }\n);\n";
+        assert_eq!(
+            ranges(text, FileKind::BlockMeshDict),
+            [(0, 5, None), (2, 4, None)]
+        );
+    }
+}
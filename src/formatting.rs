@@ -0,0 +1,557 @@
+use std::collections::HashMap;
+use std::ops::Range as Indices;
+
+use serde::Deserialize;
+use tower_lsp::lsp_types::{Position, Range, TextEdit};
+
+use crate::ast::{Dictionary, Entry, Value, Word};
+use crate::document::Document;
+use crate::parser::{Span, Token};
+
+/// The line between the `FoamFile` header and the entries.
+const SEPARATOR: &str =
+    "// * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * //";
+
+/// The line closing a dictionary file.
+const FOOTER: &str =
+    "// ************************************************************************* //";
+
+/// How documents are formatted, read from the `ofoam_ls.format` settings of each project.
+/// The defaults are the style of the OpenFOAM tutorials.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FormatOptions {
+    /// Spaces per level of nesting
+    pub indent: usize,
+    /// Width keys are padded to, so that values start in the same column
+    pub value_column: usize,
+    /// Width keys are padded to in the `FoamFile` header
+    pub header_value_column: usize,
+    /// Whether dictionary files get the banner, the separator after the header and the
+    /// closing line
+    pub banner: bool,
+    /// The OpenFOAM version the banner names
+    pub version: String,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent: 4,
+            value_column: 16,
+            header_value_column: 12,
+            banner: true,
+            version: String::new(),
+        }
+    }
+}
+
+/// Edits reformatting the whole document: entries indented by nesting with their values
+/// aligned, one blank line between dictionaries and the banner around dictionary files.
+/// Comments are kept, and lines holding nothing but numbers in a list, such as mesh points
+/// or `nonuniform` values, are left as they are.
+pub fn format(document: &Document, options: &FormatOptions) -> Vec<TextEdit> {
+    let mut text = format_lines(document, options).concat().join("\n");
+    text.push('\n');
+    if text == document.text {
+        return Vec::new();
+    }
+    vec![TextEdit {
+        range: Range::new(
+            Position::new(0, 0),
+            document.position_at(document.text.len()),
+        ),
+        new_text: text,
+    }]
+}
+
+/// Edits formatting the lines `range` touches, together with the blank lines above them, for
+/// formatting a selected sub-dictionary.
+pub fn format_range(document: &Document, range: Range, options: &FormatOptions) -> Vec<TextEdit> {
+    let lines = lines(document);
+    let mut first = (range.start.line as usize).min(lines.len() - 1);
+    while first > 0 && lines[first - 1].is_blank() {
+        first -= 1;
+    }
+    let mut last = (range.end.line as usize).min(lines.len() - 1);
+    if range.end.character == 0 && last > range.start.line as usize {
+        last -= 1;
+    }
+
+    let formatted = format_lines(document, options);
+    let mut text = formatted[first..=last].concat().join("\n");
+    text.push('\n');
+    let start = lines[first].start;
+    let end = lines
+        .get(last + 1)
+        .map_or(document.text.len(), |next| next.start);
+    if text == document.text[start..end] {
+        return Vec::new();
+    }
+    vec![TextEdit {
+        range: document.range_of(Span { start, end }),
+        new_text: text,
+    }]
+}
+
+/// The edit re-aligning the values of the entry at `position` once its `;` is typed, or
+/// re-indenting its line once a `}` is. Only what changed is replaced, to leave the cursor
+/// where it is.
+pub fn format_on_type(
+    document: &Document,
+    position: Position,
+    options: &FormatOptions,
+) -> Vec<TextEdit> {
+    let lines = lines(document);
+    let Some(line) = lines.get(position.line as usize) else {
+        return Vec::new();
+    };
+    if line.is_blank() {
+        return Vec::new();
+    }
+    let formatted = format_line(document, line, &entries(document), options);
+
+    let prefix = line
+        .text
+        .char_indices()
+        .zip(formatted.chars())
+        .find(|((_, old), new)| old != new)
+        .map(|((index, _), _)| index)
+        .unwrap_or(line.text.len().min(formatted.len()));
+    let suffix = line.text[prefix..]
+        .chars()
+        .rev()
+        .zip(formatted[prefix..].chars().rev())
+        .take_while(|(old, new)| old == new)
+        .map(|(old, _)| old.len_utf8())
+        .sum::<usize>();
+    let old = &line.text[prefix..line.text.len() - suffix];
+    let new = &formatted[prefix..formatted.len() - suffix];
+    if old == new {
+        return Vec::new();
+    }
+    vec![TextEdit {
+        range: document.range_of(Span {
+            start: line.start + prefix,
+            end: line.start + prefix + old.len(),
+        }),
+        new_text: new.to_string(),
+    }]
+}
+
+/// A line of the document as formatting sees it.
+struct Line<'a> {
+    text: &'a str,
+    start: usize,
+    /// The brackets open where the line starts, innermost last
+    open: Vec<Token>,
+    /// The tokens starting on the line
+    tokens: Indices<usize>,
+    /// Whether the line is part of a comment, string or code block spanning several lines
+    verbatim: bool,
+}
+
+impl Line<'_> {
+    fn is_blank(&self) -> bool {
+        !self.verbatim && self.text.trim().is_empty()
+    }
+
+    fn first(&self, document: &Document) -> Option<Token> {
+        document.tokens[self.tokens.clone()].first().copied()
+    }
+
+    /// The last token that is not a comment.
+    fn last(&self, document: &Document) -> Option<Token> {
+        document.tokens[self.tokens.clone()]
+            .iter()
+            .rev()
+            .find(|token| !matches!(token, Token::LineComment | Token::BlockComment))
+            .copied()
+    }
+
+    fn is_separator(&self) -> bool {
+        self.text.starts_with("// * * *")
+    }
+
+    fn is_footer(&self) -> bool {
+        self.text.starts_with("// ****")
+    }
+}
+
+fn lines(document: &Document) -> Vec<Line<'_>> {
+    let text = document.text.strip_suffix('\n').unwrap_or(&document.text);
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for text in text.split('\n') {
+        lines.push(Line {
+            text: text.strip_suffix('\r').unwrap_or(text),
+            start,
+            open: Vec::new(),
+            tokens: 0..0,
+            verbatim: false,
+        });
+        start += text.len() + 1;
+    }
+
+    let mut open = Vec::new();
+    let mut index = 0;
+    for line in 0..lines.len() {
+        let end = lines
+            .get(line + 1)
+            .map_or(usize::MAX, |next| next.start - 1);
+        lines[line].open = open.clone();
+        let first = index;
+        while index < document.tokens.len() && document.spans[index].start < end {
+            match document.tokens[index] {
+                Token::LeftBrace | Token::LeftParen => open.push(document.tokens[index]),
+                Token::RightBrace | Token::RightParen => {
+                    open.pop();
+                }
+                _ => {}
+            }
+            let span = document.spans[index];
+            if span.end > end {
+                for spanned in lines[line..]
+                    .iter_mut()
+                    .take_while(|spanned| spanned.start < span.end)
+                {
+                    spanned.verbatim = true;
+                }
+            }
+            index += 1;
+        }
+        lines[line].tokens = first..index;
+    }
+    lines
+}
+
+/// For each line of the document, the lines replacing it.
+fn format_lines(document: &Document, options: &FormatOptions) -> Vec<Vec<String>> {
+    let lines = lines(document);
+    let entries = entries(document);
+
+    let mut formatted: Vec<Vec<String>> = Vec::with_capacity(lines.len());
+    let mut previous: Option<usize> = None;
+    for (index, line) in lines.iter().enumerate() {
+        if line.is_blank() {
+            formatted.push(Vec::new());
+            continue;
+        }
+        let mut output = Vec::new();
+        if let Some(previous) = previous {
+            let blanks = blanks_between(document, &lines, previous, index);
+            output.extend(std::iter::repeat_n(String::new(), blanks));
+        }
+        output.push(format_line(document, line, &entries, options));
+        formatted.push(output);
+        previous = Some(index);
+    }
+
+    let header = document
+        .ast
+        .entries
+        .iter()
+        .find(|entry| matches!(entry, Entry::Dict { key, .. } if key.text == "FoamFile"));
+    if options.banner
+        && let Some(header) = header
+    {
+        let has_banner = document
+            .tokens
+            .iter()
+            .zip(&document.spans)
+            .take_while(|(_, span)| span.start < header.span().start)
+            .any(|(token, _)| *token == Token::BlockComment);
+        if !has_banner {
+            let first = formatted.iter().position(|output| !output.is_empty());
+            if let Some(first) = first {
+                formatted[first].splice(0..0, banner(&options.version));
+            }
+        }
+
+        let end = line_of(&lines, header.span().end.saturating_sub(1));
+        let next = (end + 1..lines.len()).find(|&index| !lines[index].is_blank());
+        if !next.is_some_and(|next| lines[next].is_separator()) {
+            formatted[end].push(SEPARATOR.to_string());
+            if let Some(next) = next
+                && formatted[next].first().is_some_and(|line| !line.is_empty())
+            {
+                formatted[next].insert(0, String::new());
+            }
+        }
+
+        let last = (0..lines.len())
+            .rev()
+            .find(|&index| !lines[index].is_blank());
+        if let Some(last) = last
+            && !lines[last].is_footer()
+        {
+            formatted[last].extend([String::new(), String::new(), FOOTER.to_string()]);
+        }
+    }
+    formatted
+}
+
+/// How many blank lines go between two lines with nothing but blank lines between them.
+fn blanks_between(document: &Document, lines: &[Line], previous: usize, index: usize) -> usize {
+    let (before, line) = (&lines[previous], &lines[index]);
+    let blanks = index - previous - 1;
+    if line.is_footer() {
+        // The tutorials leave two blank lines, files written by OpenFOAM one
+        return blanks.clamp(1, 2);
+    }
+    if line.is_separator()
+        || matches!(
+            before.last(document),
+            Some(Token::LeftBrace | Token::LeftParen)
+        )
+        || matches!(
+            line.first(document),
+            Some(Token::RightBrace | Token::RightParen)
+        )
+    {
+        return 0;
+    }
+    // Dictionaries are set apart from what follows, except in lists such as the patches of
+    // `blockMeshDict`
+    let closes_dict = before.last(document) == Some(Token::RightBrace)
+        && lines[previous + 1].open.last() != Some(&Token::LeftParen);
+    usize::from(blanks > 0 || before.is_separator() || closes_dict)
+}
+
+fn format_line(
+    document: &Document,
+    line: &Line,
+    entries: &HashMap<usize, (&Entry, bool)>,
+    options: &FormatOptions,
+) -> String {
+    let tokens = &document.tokens[line.tokens.clone()];
+    if line.verbatim || is_payload(line, tokens) {
+        return line.text.to_string();
+    }
+    let closes = matches!(tokens.first(), Some(Token::RightBrace | Token::RightParen));
+    let depth = line.open.len().saturating_sub(usize::from(closes));
+    let indent = " ".repeat(depth * options.indent);
+
+    let body = line.text.trim();
+    let start = line.start + (line.text.len() - line.text.trim_start().len());
+    let end = line.start + line.text.trim_end().len();
+    let Some((Entry::Value { key, span, .. }, header)) = entries.get(&start) else {
+        return format!("{indent}{body}");
+    };
+    if span.end > end {
+        return format!("{indent}{body}");
+    }
+    let column = if *header {
+        options.header_value_column
+    } else {
+        options.value_column
+    };
+    let mut formatted = format!("{indent}{}", format_entry(document, key, *span, column));
+    let rest = document.text[span.end..end].trim();
+    if !rest.is_empty() {
+        formatted.push(' ');
+        formatted.push_str(rest);
+    }
+    formatted
+}
+
+/// `key value ...;` with the key padded to `column` and single spaces between the values.
+fn format_entry(document: &Document, key: &Word, span: Span, column: usize) -> String {
+    let text = &document.text;
+    let mut formatted = text[key.span.start..key.span.end].to_string();
+    let first = document
+        .spans
+        .partition_point(|token| token.start < key.span.end);
+    let mut previous = key.span.end;
+    for (token, token_span) in document.tokens[first..]
+        .iter()
+        .zip(&document.spans[first..])
+        .take_while(|(_, token_span)| token_span.end <= span.end)
+    {
+        if previous == key.span.end && *token != Token::Semicolon {
+            let width = formatted.chars().count();
+            formatted.push_str(&" ".repeat(column.saturating_sub(width).max(1)));
+        } else if token_span.start > previous && *token != Token::Semicolon {
+            formatted.push(' ');
+        }
+        formatted.push_str(&text[token_span.start..token_span.end]);
+        previous = token_span.end;
+    }
+    formatted
+}
+
+/// Lines of nothing but numbers in a list, such as mesh points and `nonuniform` values.
+fn is_payload(line: &Line, tokens: &[Token]) -> bool {
+    line.open.last() == Some(&Token::LeftParen)
+        && tokens.iter().all(|token| {
+            matches!(
+                token,
+                Token::Int(_)
+                    | Token::Float(_)
+                    | Token::Minus
+                    | Token::Plus
+                    | Token::LeftParen
+                    | Token::RightParen
+            )
+        })
+}
+
+/// The `key value;` entries by the offset they start at, and whether they are in the
+/// `FoamFile` header.
+fn entries(document: &Document) -> HashMap<usize, (&Entry, bool)> {
+    let mut entries = HashMap::new();
+    one_line_entries(&document.ast, true, &mut entries);
+    entries
+}
+
+fn one_line_entries<'a>(
+    dict: &'a Dictionary,
+    top: bool,
+    entries: &mut HashMap<usize, (&'a Entry, bool)>,
+) {
+    for entry in &dict.entries {
+        match entry {
+            Entry::Value { values, span, .. } => {
+                entries.insert(span.start, (entry, false));
+                in_values(values, entries);
+            }
+            Entry::Dict { key, dict, .. } if top && key.text == "FoamFile" => {
+                for entry in &dict.entries {
+                    if let Entry::Value { span, .. } = entry {
+                        entries.insert(span.start, (entry, true));
+                    }
+                }
+            }
+            Entry::Dict { dict, .. } => one_line_entries(dict, false, entries),
+            Entry::Bare { values, .. } | Entry::Directive { args: values, .. } => {
+                in_values(values, entries)
+            }
+            Entry::Macro { .. } => {}
+        }
+    }
+}
+
+fn in_values<'a>(values: &'a [Value], entries: &mut HashMap<usize, (&'a Entry, bool)>) {
+    for value in values {
+        match value {
+            Value::List { items, .. } => in_values(items, entries),
+            Value::Dict(dict) => one_line_entries(dict, false, entries),
+            _ => {}
+        }
+    }
+}
+
+fn line_of(lines: &[Line], offset: usize) -> usize {
+    lines
+        .partition_point(|line| line.start <= offset)
+        .saturating_sub(1)
+}
+
+fn banner(version: &str) -> Vec<String> {
+    [
+        r"/*--------------------------------*- C++ -*----------------------------------*\",
+        r"  =========                 |",
+        r"  \\      /  F ield         | OpenFOAM: The Open Source CFD Toolbox",
+        r"   \\    /   O peration     | Website:  https://openfoam.org",
+        &format!(r"    \\  /    A nd           | Version:  {version}"),
+        r"     \\/     M anipulation  |",
+        r"\*---------------------------------------------------------------------------*/",
+    ]
+    .map(|line| line.trim_end().to_string())
+    .to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_kind::{FieldClass, FileKind};
+
+    fn formatted(text: &str, kind: FileKind, options: &FormatOptions) -> String {
+        let document = Document::new(text.to_string(), 0, kind);
+        match format(&document, options).as_slice() {
+            [] => text.to_string(),
+            [edit] => edit.new_text.clone(),
+            edits => panic!("unexpected edits {edits:?}"),
+        }
+    }
+
+    #[test]
+    fn test_format_in_house_style() {
+        let text = "FoamFile\n{\n  format ascii;\n    object   fvSchemes;\n}\nddtSchemes\n{\n\n        default Euler ;   // first order\n}\n\n\n\ngradSchemes { default Gauss linear; }\ndivSchemes\n{\n    div(phi,U)  Gauss   linear;\n    /* upwind\n       for now */\n    div(phi,k) Gauss upwind;\n}\n";
+        let expected = [
+            &banner("11").join("\n"),
+            "FoamFile\n{\n    format      ascii;\n    object      fvSchemes;\n}",
+            SEPARATOR,
+            "\nddtSchemes\n{\n    default         Euler; // first order\n}\n",
+            "gradSchemes { default Gauss linear; }\n",
+            "divSchemes\n{\n    div(phi,U)      Gauss linear;\n    /* upwind\n       for now */\n    div(phi,k)      Gauss upwind;\n}\n\n",
+            FOOTER,
+            "",
+        ]
+        .join("\n");
+        let options = FormatOptions {
+            version: "11".to_string(),
+            ..FormatOptions::default()
+        };
+        let once = formatted(text, FileKind::FvSchemes, &options);
+        assert_eq!(once, expected);
+        assert_eq!(formatted(&once, FileKind::FvSchemes, &options), once);
+    }
+
+    #[test]
+    fn test_numeric_payloads_are_not_reflowed() {
+        let text = "internalField   nonuniform List<scalar>\n3\n(\n0.1\n0.2  \n-0.3\n)\n;\nboundaryField\n{\ninlet\n{\ntype fixedValue;\nvalue uniform (1  0 0);\n}\n}\n";
+        let options = FormatOptions {
+            banner: false,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            formatted(text, FileKind::Field(Some(FieldClass::Scalar)), &options),
+            "internalField   nonuniform List<scalar>\n3\n(\n0.1\n0.2  \n-0.3\n)\n;\nboundaryField\n{\n    inlet\n    {\n        type            fixedValue;\n        value           uniform (1 0 0);\n    }\n}\n"
+        );
+    }
+
+    fn apply(document: &Document, edits: &[TextEdit]) -> String {
+        let mut text = document.text.clone();
+        for edit in edits.iter().rev() {
+            let start = document.offset_at(edit.range.start);
+            let end = document.offset_at(edit.range.end);
+            text.replace_range(start..end, &edit.new_text);
+        }
+        text
+    }
+
+    #[test]
+    fn test_format_a_selected_dictionary() {
+        let text =
+            "ddtSchemes\n{\ndefault Euler;\n}\n\n\n\ngradSchemes\n{\ndefault Gauss linear;\n}\n";
+        let document = Document::new(text.to_string(), 0, FileKind::FvSchemes);
+        let range = Range::new(Position::new(7, 0), Position::new(10, 1));
+        let edits = format_range(&document, range, &FormatOptions::default());
+        assert_eq!(
+            apply(&document, &edits),
+            "ddtSchemes\n{\ndefault Euler;\n}\n\ngradSchemes\n{\n    default         Gauss linear;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_format_on_type() {
+        let options = FormatOptions::default();
+        let text =
+            "solvers\n{\n    p\n    {\n        solver  PCG;\n        tolerance 1e-06;\n  }\n}\n";
+        let document = Document::new(text.to_string(), 0, FileKind::FvSolution);
+        let edits = format_on_type(&document, Position::new(5, 24), &options);
+        assert_eq!(
+            edits,
+            [TextEdit {
+                range: Range::new(Position::new(5, 18), Position::new(5, 18)),
+                new_text: "      ".to_string(),
+            }]
+        );
+        let edits = format_on_type(&document, Position::new(6, 3), &options);
+        assert_eq!(
+            apply(&document, &edits),
+            "solvers\n{\n    p\n    {\n        solver  PCG;\n        tolerance 1e-06;\n    }\n}\n"
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use std::any::Any;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+
+use futures::FutureExt;
+use tower_lsp::jsonrpc::{Error, ErrorCode, Result};
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+
+/// Await a request handler, turning a panic into an `InternalError` response so that one bad
+/// request cannot take the whole server down.
+pub async fn request<T>(
+    method: &'static str,
+    handler: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    match AssertUnwindSafe(handler).catch_unwind().await {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = panic_message(payload.as_ref());
+            tracing::error!(method, %message, "request handler panicked");
+            Err(Error {
+                code: ErrorCode::InternalError,
+                message: format!("{method} failed: {message}").into(),
+                data: None,
+            })
+        }
+    }
+}
+
+/// Await a notification handler, logging and swallowing any panic.
+pub async fn notification(method: &'static str, handler: impl Future<Output = ()>) {
+    if let Err(payload) = AssertUnwindSafe(handler).catch_unwind().await {
+        let message = panic_message(payload.as_ref());
+        tracing::error!(method, %message, "notification handler panicked");
+    }
+}
+
+/// Recover the message passed to `panic!`, if there was one.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// The diagnostic shown at the top of a document whose analysis failed.
+pub fn health_diagnostic(message: &str) -> Diagnostic {
+    Diagnostic {
+        range: Range::new(Position::new(0, 0), Position::new(0, 0)),
+        severity: Some(DiagnosticSeverity::WARNING),
+        code: Some(NumberOrString::String("analysis-failed".to_string())),
+        source: Some("Foam Language Server".to_string()),
+        message: format!(
+            "Analysis of this file failed ({message}). Results may be incomplete until it is re-analysed."
+        ),
+        ..Diagnostic::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_panicking_request_becomes_internal_error() {
+        let result: Result<()> = request("test/request", async { panic!("boom") }).await;
+
+        let error = result.unwrap_err();
+        assert_eq!(error.code, ErrorCode::InternalError);
+        assert_eq!(error.message, "test/request failed: boom");
+    }
+}
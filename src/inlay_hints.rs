@@ -0,0 +1,375 @@
+use serde::{Deserialize, Serialize};
+use tower_lsp::lsp_types::{
+    InlayHint, InlayHintKind, InlayHintLabel, InlayHintTooltip, MarkupContent, MarkupKind, Range,
+    Url,
+};
+
+use crate::ast::{Dictionary, Entry, Value};
+use crate::document::Document;
+use crate::file_kind::{FieldClass, FileKind};
+use crate::parser::{self, Span};
+
+/// Macro values longer than this are cut short in the hint; the tooltip has them in full.
+const MAX_VALUE_LENGTH: usize = 32;
+
+/// Lists of more numbers than this get a count, as do lists of lists and dictionaries.
+const COUNTED_LIST_LENGTH: usize = 9;
+
+/// How many macros deep to follow `$a` referring to `$b` referring to a value.
+const MAX_MACRO_DEPTH: usize = 8;
+
+const VECTOR_COMPONENTS: &[&str] = &["x", "y", "z"];
+const SYMM_TENSOR_COMPONENTS: &[&str] = &["xx", "xy", "xz", "yy", "yz", "zz"];
+const TENSOR_COMPONENTS: &[&str] = &["xx", "xy", "xz", "yx", "yy", "yz", "zx", "zy", "zz"];
+
+/// Entries whose value is a point or direction outside of field files.
+const VECTOR_KEYS: &[&str] = &[
+    "CofR",
+    "origin",
+    "point",
+    "normal",
+    "axis",
+    "e1",
+    "e2",
+    "e3",
+    "liftDir",
+    "dragDir",
+    "pitchAxis",
+    "locationInMesh",
+    "p1",
+    "p2",
+];
+
+/// What `inlayHint/resolve` needs to fill in a tooltip.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+enum HintData {
+    Unit { label: String },
+    Macro { uri: Url, offset: usize },
+}
+
+/// The hints for the part of the document within `range`.
+pub fn hints(document: &Document, uri: &Url, range: Range) -> Vec<InlayHint> {
+    let start = document.offset_at(range.start);
+    let end = document.offset_at(range.end);
+
+    let mut collector = Collector {
+        document,
+        uri,
+        hints: Vec::new(),
+    };
+    collector.units();
+    collector.dictionary(&document.ast);
+
+    let mut hints: Vec<_> = collector
+        .hints
+        .into_iter()
+        .filter(|(offset, _)| (start..=end).contains(offset))
+        .collect();
+    hints.sort_by_key(|(offset, _)| *offset);
+    hints.into_iter().map(|(_, hint)| hint).collect()
+}
+
+/// Fill in the tooltip of a hint returned by `hints`. `with_document` runs a function on the
+/// document a macro hint belongs to.
+pub fn resolve<F>(mut hint: InlayHint, with_document: F) -> InlayHint
+where
+    F: FnOnce(&Url, &dyn Fn(&Document) -> Option<String>) -> Option<String>,
+{
+    let Some(data) = hint
+        .data
+        .take()
+        .and_then(|data| serde_json::from_value::<HintData>(data).ok())
+    else {
+        return hint;
+    };
+
+    let tooltip = match data {
+        HintData::Unit { label } => unit_description(&label).map(str::to_string),
+        HintData::Macro { uri, offset } => {
+            with_document(&uri, &|document| macro_tooltip(document, offset))
+        }
+    };
+    hint.tooltip = tooltip.map(|value| {
+        InlayHintTooltip::MarkupContent(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        })
+    });
+    hint
+}
+
+fn unit_description(label: &str) -> Option<&'static str> {
+    Some(match label {
+        "kg" => "Mass, in kilograms",
+        "m" => "Length, in metres",
+        "s" => "Time, in seconds",
+        "K" => "Temperature, in kelvin",
+        "mol" => "Amount of substance, in moles",
+        "A" => "Current, in amperes",
+        "cd" => "Luminous intensity, in candela",
+        _ => return None,
+    })
+}
+
+/// The definition a macro at `offset` resolves to, and where it is.
+fn macro_tooltip(document: &Document, offset: usize) -> Option<String> {
+    let (_, span) = document.token_at(offset)?;
+    let name = &document.text[span.start..span.end];
+    let entry = document.ast.resolve_macro(name, offset)?;
+    let definition = entry.span();
+    Some(format!(
+        "```\n{}\n```\nDefined on line {}",
+        &document.text[definition.start..definition.end],
+        document.position_at(definition.start).line + 1
+    ))
+}
+
+struct Collector<'a> {
+    document: &'a Document,
+    uri: &'a Url,
+    /// Hints with the offset they are shown at
+    hints: Vec<(usize, InlayHint)>,
+}
+
+impl Collector<'_> {
+    fn push(
+        &mut self,
+        offset: usize,
+        label: String,
+        kind: Option<InlayHintKind>,
+        data: Option<HintData>,
+    ) {
+        let hint = InlayHint {
+            position: self.document.position_at(offset),
+            label: InlayHintLabel::String(label),
+            kind,
+            text_edits: None,
+            tooltip: None,
+            padding_left: None,
+            padding_right: None,
+            data: data.and_then(|data| serde_json::to_value(data).ok()),
+        };
+        self.hints.push((offset, hint));
+    }
+
+    /// Unit labels in front of each exponent of a `dimensions` entry.
+    fn units(&mut self) {
+        let labels = parser::get_inline_hints(&self.document.tokens, &self.document.spans);
+        for (span, label) in labels {
+            self.push(
+                span.start,
+                format!("{label}:"),
+                Some(InlayHintKind::PARAMETER),
+                Some(HintData::Unit { label }),
+            );
+        }
+    }
+
+    fn dictionary(&mut self, dict: &Dictionary) {
+        for entry in &dict.entries {
+            match entry {
+                Entry::Dict { dict, .. } => self.dictionary(dict),
+                Entry::Value { key, values, .. } => self.values(Some(&key.text), values),
+                Entry::Bare { values, .. } => self.values(None, values),
+                Entry::Directive { .. } | Entry::Macro { .. } => {}
+            }
+        }
+    }
+
+    fn values(&mut self, key: Option<&str>, values: &[Value]) {
+        let mut after_uniform = false;
+        for value in values {
+            match value {
+                Value::Macro(name) => self.macro_value(name.span),
+                Value::List { items, span } => {
+                    let components = if after_uniform {
+                        self.field_components(items.len())
+                    } else if key.is_some_and(|key| VECTOR_KEYS.contains(&key))
+                        || (key == Some("value") && self.document.kind == FileKind::Gravity)
+                    {
+                        VECTOR_COMPONENTS
+                    } else {
+                        &[]
+                    };
+                    if components.len() == items.len() && all_numbers(items) {
+                        self.components(items, components);
+                    } else {
+                        self.list(items, *span);
+                    }
+                }
+                Value::Dict(dict) => self.dictionary(dict),
+                _ => {}
+            }
+            after_uniform = matches!(value, Value::Word(word) if word.text == "uniform");
+        }
+    }
+
+    /// Component names for a `uniform` value of `length` numbers in this file.
+    fn field_components(&self, length: usize) -> &'static [&'static str] {
+        let class = match self.document.kind {
+            FileKind::Field(class) => class,
+            _ => None,
+        };
+        match (class, length) {
+            (Some(FieldClass::Vector) | None, 3) => VECTOR_COMPONENTS,
+            (Some(FieldClass::SymmTensor) | None, 6) => SYMM_TENSOR_COMPONENTS,
+            (Some(FieldClass::Tensor) | None, 9) => TENSOR_COMPONENTS,
+            _ => &[],
+        }
+    }
+
+    fn components(&mut self, items: &[Value], names: &[&str]) {
+        for (item, name) in items.iter().zip(names) {
+            self.push(
+                item.span().start,
+                format!("{name}:"),
+                Some(InlayHintKind::PARAMETER),
+                None,
+            );
+        }
+    }
+
+    /// A count for lists of like items, then hints for the items themselves. Mixed lists
+    /// such as `blocks` are not counted, as their length says little.
+    fn list(&mut self, items: &[Value], span: Span) {
+        let count = if !items.is_empty()
+            && items
+                .iter()
+                .all(|item| matches!(item, Value::List { .. } | Value::Dict(_)))
+        {
+            Some(items.len())
+        } else if is_named_dicts(items) {
+            Some(items.len() / 2)
+        } else if items.len() > COUNTED_LIST_LENGTH && all_numbers(items) {
+            Some(items.len())
+        } else {
+            None
+        };
+        if let Some(count) = count {
+            let noun = if count == 1 { "item" } else { "items" };
+            self.push(span.start, format!("{count} {noun}"), None, None);
+        }
+        for item in items {
+            match item {
+                Value::List { items, span } => self.list(items, *span),
+                Value::Dict(dict) => self.dictionary(dict),
+                Value::Macro(name) => self.macro_value(name.span),
+                _ => {}
+            }
+        }
+    }
+
+    /// The value a macro stands for, shown after it.
+    fn macro_value(&mut self, span: Span) {
+        let Some(value) = self.resolve(span) else {
+            return;
+        };
+        let value = if value.chars().count() > MAX_VALUE_LENGTH {
+            let cut: String = value.chars().take(MAX_VALUE_LENGTH).collect();
+            format!("{cut}…")
+        } else {
+            value
+        };
+
+        let data = HintData::Macro {
+            uri: self.uri.clone(),
+            offset: span.start,
+        };
+        self.push(span.end, format!("= {value}"), None, Some(data));
+        if let Some((_, hint)) = self.hints.last_mut() {
+            hint.padding_left = Some(true);
+        }
+    }
+
+    /// The source text of the values a macro refers to, following macros to macros.
+    fn resolve(&self, span: Span) -> Option<String> {
+        let text = &self.document.text;
+        let ast = &self.document.ast;
+        let mut name = &text[span.start..span.end];
+        let mut offset = span.start;
+
+        for _ in 0..MAX_MACRO_DEPTH {
+            let entry = ast.resolve_macro(name, offset)?;
+            match entry {
+                Entry::Value { values, .. } => {
+                    if let [Value::Macro(next)] = values.as_slice() {
+                        name = &text[next.span.start..next.span.end];
+                        offset = next.span.start;
+                        continue;
+                    }
+                    let first = values.first()?.span();
+                    let last = values.last()?.span();
+                    let source = &text[first.start..last.end];
+                    return Some(source.split_whitespace().collect::<Vec<_>>().join(" "));
+                }
+                Entry::Dict { .. } => return Some("{ … }".to_string()),
+                _ => return None,
+            }
+        }
+        None
+    }
+}
+
+/// Whether the list pairs names with dictionaries, as the patch list of `blockMeshDict`.
+fn is_named_dicts(items: &[Value]) -> bool {
+    !items.is_empty()
+        && items.len().is_multiple_of(2)
+        && items
+            .chunks(2)
+            .all(|pair| matches!(pair, [Value::Word(_), Value::Dict(_)]))
+}
+
+fn all_numbers(items: &[Value]) -> bool {
+    items.iter().all(|item| matches!(item, Value::Number(..)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::lsp_types::Position;
+
+    fn labels(text: &str, kind: FileKind) -> Vec<String> {
+        let document = Document::new(text.to_string(), 0, kind);
+        let uri = Url::parse("file:///case/0/U").unwrap();
+        let range = Range::new(Position::new(0, 0), Position::new(u32::MAX, 0));
+        hints(&document, &uri, range)
+            .into_iter()
+            .map(|hint| match hint.label {
+                InlayHintLabel::String(label) => label,
+                InlayHintLabel::LabelParts(_) => String::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_field_hints() {
+        let text = "dimensions [0 1 -1 0 0 0 0];\ninternalField uniform (1 0 0);\nboundaryField\n{\n    inlet { type fixedValue; value $internalField; }\n}\n";
+        let labels = labels(text, FileKind::Field(Some(FieldClass::Vector)));
+
+        assert_eq!(
+            labels,
+            vec![
+                "kg:",
+                "m:",
+                "s:",
+                "K:",
+                "mol:",
+                "A:",
+                "cd:",
+                "x:",
+                "y:",
+                "z:",
+                "= uniform (1 0 0)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_counts() {
+        let text = "vertices\n(\n    (0 0 0)\n    (1 0 0)\n);\nblocks (hex (0 1 2 3 4 5 6 7) (20 20 1) simpleGrading (1 1 1));\nboundary (inlet { type patch; } outlet { type patch; });\n";
+        let labels = labels(text, FileKind::BlockMeshDict);
+
+        assert_eq!(labels, vec!["2 items", "2 items"]);
+    }
+}
@@ -0,0 +1,299 @@
+use std::path::{Path, PathBuf};
+
+use tower_lsp::lsp_types::{DocumentLink, Url};
+
+use crate::ast::{Dictionary, Entry, Value, Word};
+use crate::case::{self, Case};
+use crate::document::Document;
+use crate::file_kind::FileKind;
+use crate::parser::Span;
+
+/// Where `snappyHexMeshDict` finds its geometry files, by OpenFOAM version.
+const GEOMETRY_DIRS: &[&str] = &["constant/triSurface", "constant/geometry"];
+
+/// Where `#includeFunc` finds function object templates below an `etc` directory.
+const FUNCTION_TEMPLATES: &str = "caseDicts/postProcessing";
+
+/// How deep below `FUNCTION_TEMPLATES` to look for a template.
+const MAX_TEMPLATE_DEPTH: usize = 4;
+
+/// Prefixes marking a path within the case, wherever it appears.
+const CASE_PREFIXES: &[&str] = &[
+    "$FOAM_CASE/",
+    "${FOAM_CASE}/",
+    "<case>/",
+    "<system>/",
+    "<constant>/",
+];
+
+/// The OpenFOAM `etc` directories in the order OpenFOAM searches them: the user's
+/// `~/.OpenFOAM`, then the installation's `$FOAM_ETC` or `$WM_PROJECT_DIR/etc`.
+pub fn etc_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(home) = std::env::var_os("HOME") {
+        dirs.push(Path::new(&home).join(".OpenFOAM"));
+    }
+    if let Some(etc) = std::env::var_os("FOAM_ETC") {
+        dirs.push(PathBuf::from(etc));
+    } else if let Some(project) = std::env::var_os("WM_PROJECT_DIR") {
+        dirs.push(Path::new(&project).join("etc"));
+    }
+    dirs
+}
+
+/// Links to the files a document reads: its includes, the function objects and `etc` files
+/// it includes, geometry, table files and paths within the case. Only files that exist are
+/// linked.
+pub fn document_links(document: &Document, path: &Path, etc: &[PathBuf]) -> Vec<DocumentLink> {
+    let dir = path.parent().unwrap_or(Path::new("/"));
+    let mut linker = Linker {
+        document,
+        root: Case::root_of(path).unwrap_or_else(|| dir.to_path_buf()),
+        dir,
+        etc,
+        links: Vec::new(),
+    };
+    linker.dictionary(&document.ast);
+    linker.links
+}
+
+struct Linker<'a> {
+    document: &'a Document,
+    root: PathBuf,
+    dir: &'a Path,
+    etc: &'a [PathBuf],
+    links: Vec<DocumentLink>,
+}
+
+impl Linker<'_> {
+    fn dictionary(&mut self, dict: &Dictionary) {
+        for entry in &dict.entries {
+            match entry {
+                Entry::Directive { name, args, .. } => self.directive(&name.text, args),
+                Entry::Dict { key, dict, .. }
+                    if key.text == "geometry"
+                        && self.document.kind == FileKind::SnappyHexMeshDict =>
+                {
+                    self.geometry(dict)
+                }
+                Entry::Dict { dict, .. } => self.dictionary(dict),
+                Entry::Value { key, values, .. } => {
+                    // A `Function1` reading a table, `file "<constant>/inlet.csv";`
+                    if key.text == "file"
+                        && let Some(Value::String(file) | Value::Word(file)) = values.first()
+                    {
+                        let path = case::resolve_path(&self.root, &self.root, &file.text);
+                        self.link(file, path);
+                    } else {
+                        self.values(values);
+                    }
+                }
+                Entry::Bare { values, .. } => self.values(values),
+                Entry::Macro { .. } => {}
+            }
+        }
+    }
+
+    fn values(&mut self, values: &[Value]) {
+        for value in values {
+            match value {
+                Value::String(word) | Value::Word(word)
+                    if CASE_PREFIXES
+                        .iter()
+                        .any(|prefix| word.text.starts_with(prefix)) =>
+                {
+                    let path = case::resolve_path(&self.root, &self.root, &word.text);
+                    self.link(word, path);
+                }
+                Value::List { items, .. } => self.values(items),
+                Value::Dict(dict) => self.dictionary(dict),
+                _ => {}
+            }
+        }
+    }
+
+    fn directive(&mut self, name: &str, args: &[Value]) {
+        let Some(Value::String(file) | Value::Word(file)) = args.first() else {
+            return;
+        };
+        match name {
+            "#include" | "#includeIfPresent" => {
+                let path = case::resolve_path(&self.root, self.dir, &file.text);
+                self.link(file, path);
+            }
+            "#includeEtc" => {
+                if let Some(path) = self
+                    .etc
+                    .iter()
+                    .map(|etc| etc.join(&file.text))
+                    .find(|path| path.is_file())
+                {
+                    self.link(file, path);
+                }
+            }
+            "#includeFunc" => {
+                // `#includeFunc streamlines` or `#includeFunc mag(U)`
+                let function = file.text.split('(').next().unwrap_or_default().trim();
+                let in_case = Some(self.root.join("system").join(function));
+                let path = in_case.filter(|path| path.is_file()).or_else(|| {
+                    self.etc.iter().find_map(|etc| {
+                        find_file(&etc.join(FUNCTION_TEMPLATES), function, MAX_TEMPLATE_DEPTH)
+                    })
+                });
+                if let Some(path) = path {
+                    let span = Span {
+                        start: file.span.start + usize::from(file.quoted),
+                        end: file.span.start + usize::from(file.quoted) + function.len(),
+                    };
+                    self.push(span, path);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Geometry files, named by the key as in `motorBike.obj { type triSurfaceMesh; }` or by
+    /// `file` as in `motorBike { type triSurfaceMesh; file "motorBike.obj"; }`.
+    fn geometry(&mut self, geometry: &Dictionary) {
+        for entry in &geometry.entries {
+            let file = match entry {
+                Entry::Dict { dict, .. } => dict.get("file").and_then(Entry::word),
+                _ => None,
+            };
+            let Some(file) = file.or(entry.key().filter(|key| key.text.contains('.'))) else {
+                continue;
+            };
+            if CASE_PREFIXES
+                .iter()
+                .any(|prefix| file.text.starts_with(prefix))
+            {
+                let path = case::resolve_path(&self.root, &self.root, &file.text);
+                self.link(file, path);
+            } else if let Some(path) = GEOMETRY_DIRS
+                .iter()
+                .map(|dir| self.root.join(dir).join(&file.text))
+                .find(|path| path.is_file())
+            {
+                self.link(file, path);
+            }
+        }
+    }
+
+    fn link(&mut self, word: &Word, path: PathBuf) {
+        let span = if word.quoted {
+            Span {
+                start: word.span.start + 1,
+                end: word.span.end - 1,
+            }
+        } else {
+            word.span
+        };
+        self.push(span, path);
+    }
+
+    fn push(&mut self, span: Span, path: PathBuf) {
+        if !path.is_file() {
+            return;
+        }
+        let Ok(target) = Url::from_file_path(&path) else {
+            return;
+        };
+        self.links.push(DocumentLink {
+            range: self.document.range_of(span),
+            target: Some(target),
+            tooltip: Some(path.display().to_string()),
+            data: None,
+        });
+    }
+}
+
+/// The file named `name` at or below `dir`.
+fn find_file(dir: &Path, name: &str, depth: usize) -> Option<PathBuf> {
+    let entries = std::fs::read_dir(dir).ok()?;
+    let mut dirs = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            dirs.push(path);
+        } else if path.file_name().is_some_and(|file| file == name) {
+            return Some(path);
+        }
+    }
+    dirs.sort();
+    if depth == 0 {
+        return None;
+    }
+    dirs.iter().find_map(|dir| find_file(dir, name, depth - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_links_to_includes_geometry_and_tables() {
+        let root = std::env::temp_dir().join(format!("ofoam_ls-links-{}", std::process::id()));
+        let etc = root.join("etc");
+        for file in [
+            "case/system/controlDict",
+            "case/system/include/mesh",
+            "case/constant/triSurface/motorBike.obj",
+            "case/constant/geometry/wing.stl",
+            "case/constant/inlet.csv",
+            "etc/caseDicts/setConstraintTypes",
+            "etc/caseDicts/postProcessing/graphs/singleGraph",
+        ] {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        let text = "#include \"include/mesh\"\n#includeEtc \"caseDicts/setConstraintTypes\"\n#includeFunc singleGraph\n#include \"missing\"\ngeometry\n{\n    motorBike.obj { type triSurfaceMesh; }\n    wing { type triSurfaceMesh; file \"wing.stl\"; }\n}\ninlet { file \"<constant>/inlet.csv\"; }\nsurface \"$FOAM_CASE/constant/triSurface/motorBike.obj\";\n";
+        let document = Document::new(text.to_string(), 0, FileKind::SnappyHexMeshDict);
+        let links = document_links(
+            &document,
+            &root.join("case/system/snappyHexMeshDict"),
+            &[etc],
+        );
+        let linked: Vec<_> = links
+            .iter()
+            .map(|link| {
+                let start = document.offset_at(link.range.start);
+                let end = document.offset_at(link.range.end);
+                let target = link.target.as_ref().unwrap().to_file_path().unwrap();
+                (
+                    text[start..end].to_string(),
+                    target
+                        .strip_prefix(&root)
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+            })
+            .collect();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            linked,
+            [
+                ("include/mesh", "case/system/include/mesh"),
+                (
+                    "caseDicts/setConstraintTypes",
+                    "etc/caseDicts/setConstraintTypes"
+                ),
+                (
+                    "singleGraph",
+                    "etc/caseDicts/postProcessing/graphs/singleGraph"
+                ),
+                ("motorBike.obj", "case/constant/triSurface/motorBike.obj"),
+                ("wing.stl", "case/constant/geometry/wing.stl"),
+                ("<constant>/inlet.csv", "case/constant/inlet.csv"),
+                (
+                    "$FOAM_CASE/constant/triSurface/motorBike.obj",
+                    "case/constant/triSurface/motorBike.obj"
+                ),
+            ]
+            .map(|(text, target)| (text.to_string(), target.to_string()))
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use std::fmt::Write as _;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use tower_lsp::lsp_types::{LogTraceParams, TraceValue};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::{EnvFilter, Targets};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{Layer, fmt};
+
+/// The client's `$/setTrace` value, and the `$/logTrace` messages waiting to be sent to it.
+#[derive(Debug)]
+pub struct LspTrace {
+    value: Mutex<TraceValue>,
+    sender: UnboundedSender<LogTraceParams>,
+    receiver: Mutex<Option<UnboundedReceiver<LogTraceParams>>>,
+}
+
+impl LspTrace {
+    pub fn new() -> Arc<Self> {
+        let (sender, receiver) = unbounded_channel();
+        Arc::new(Self {
+            value: Mutex::new(TraceValue::Off),
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+        })
+    }
+
+    pub fn value(&self) -> TraceValue {
+        *self.value.lock().unwrap()
+    }
+
+    pub fn set(&self, value: TraceValue) {
+        *self.value.lock().unwrap() = value;
+    }
+
+    /// Hand out the receiving end of the trace queue. Only the first caller gets it.
+    pub fn take_receiver(&self) -> Option<UnboundedReceiver<LogTraceParams>> {
+        self.receiver.lock().unwrap().take()
+    }
+}
+
+/// A `tracing` layer turning this crate's events into `$/logTrace` messages.
+struct LspTraceLayer {
+    trace: Arc<LspTrace>,
+}
+
+impl<S> Layer<S> for LspTraceLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let value = self.trace.value();
+        let level = *event.metadata().level();
+        if value == TraceValue::Off || (value == TraceValue::Messages && level > Level::INFO) {
+            return;
+        }
+
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let verbose = (value == TraceValue::Verbose).then(|| {
+            let mut verbose = format!("{level} {}", event.metadata().target());
+            if let Some(scope) = ctx.event_scope(event) {
+                let spans: Vec<_> = scope.from_root().map(|span| span.name()).collect();
+                let _ = write!(verbose, " [{}]", spans.join(" > "));
+            }
+            verbose.push_str(&visitor.fields);
+            verbose
+        });
+
+        let _ = self.trace.sender.send(LogTraceParams {
+            message: visitor.message,
+            verbose,
+        });
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            let _ = write!(self.fields, " {}={value}", field.name());
+        }
+    }
+}
+
+/// Install the global subscriber.
+///
+/// Logs go to `log_file` when given and to stderr otherwise, filtered by `RUST_LOG`
+/// (defaulting to `ofoam_ls=info`). Events from this crate are also forwarded to the client
+/// as `$/logTrace` notifications according to `trace`.
+pub fn init(log_file: Option<&Path>, trace: Arc<LspTrace>) -> std::io::Result<()> {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("{}=info", env!("CARGO_CRATE_NAME"))));
+
+    let output = match log_file {
+        Some(path) => {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?;
+            fmt::layer()
+                .with_ansi(false)
+                .with_writer(Mutex::new(file))
+                .boxed()
+        }
+        None => fmt::layer()
+            .with_ansi(std::io::stderr().is_terminal())
+            .with_writer(std::io::stderr)
+            .boxed(),
+    };
+
+    let lsp_trace = LspTraceLayer { trace }
+        .with_filter(Targets::new().with_target(env!("CARGO_CRATE_NAME"), Level::DEBUG));
+
+    tracing_subscriber::registry()
+        .with(output.with_filter(filter))
+        .with(lsp_trace)
+        .init();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `$/logTrace` messages sent for the events `log` emits with the trace at `value`.
+    fn traced(value: TraceValue, log: impl FnOnce()) -> Vec<LogTraceParams> {
+        let trace = LspTrace::new();
+        trace.set(value);
+        let mut receiver = trace.take_receiver().unwrap();
+        let subscriber = tracing_subscriber::registry().with(LspTraceLayer { trace });
+        tracing::subscriber::with_default(subscriber, log);
+
+        let mut messages = Vec::new();
+        while let Ok(params) = receiver.try_recv() {
+            messages.push(params);
+        }
+        messages
+    }
+
+    fn messages(value: TraceValue) -> Vec<String> {
+        traced(value, || {
+            tracing::info!("indexed case");
+            tracing::debug!("hover");
+        })
+        .into_iter()
+        .map(|params| params.message)
+        .collect()
+    }
+
+    #[test]
+    fn test_trace_value_sets_the_level() {
+        assert!(messages(TraceValue::Off).is_empty());
+        assert_eq!(messages(TraceValue::Messages), ["indexed case"]);
+        assert_eq!(messages(TraceValue::Verbose), ["indexed case", "hover"]);
+    }
+
+    #[test]
+    fn test_events_become_log_trace_params() {
+        let log = || {
+            let span = tracing::info_span!("hover");
+            let _entered = span.enter();
+            tracing::info!(line = 3, uri = "file:///case/0/U", "hovered");
+        };
+
+        let messages = traced(TraceValue::Messages, log);
+        assert_eq!(
+            messages,
+            [LogTraceParams {
+                message: "hovered".to_string(),
+                verbose: None,
+            }]
+        );
+
+        let messages = traced(TraceValue::Verbose, log);
+        assert_eq!(
+            messages,
+            [LogTraceParams {
+                message: "hovered".to_string(),
+                verbose: Some(
+                    "INFO ofoam_ls::logging::tests [hover] line=3 uri=file:///case/0/U".to_string()
+                ),
+            }]
+        );
+    }
+}
@@ -1,25 +1,45 @@
+use case::Case;
+use clap::Parser;
+use dashmap::DashMap;
+use document::{Document, Health};
+use file_kind::FileKind;
+use formatting::FormatOptions;
+use progress::ProgressTracker;
+use semantic_tokens::TokenCache;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
-use std::ops::Range;
-use std::path::PathBuf;
-use tower_lsp::jsonrpc::Result;
-use tower_lsp::lsp_types::notification::Notification;
-use tower_lsp::{Client, LanguageServer, LspService, Server};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tower_lsp::jsonrpc::{Error, Result};
+use tower_lsp::lsp_types::notification::{LogTrace, Notification};
+use tower_lsp::{Client, LanguageServer, LspService};
 use tower_lsp::{async_trait, lsp_types::*};
 
-mod analyzer;
+mod ast;
+mod case;
+mod cli;
+mod completion;
+mod definition;
+mod document;
+mod file_kind;
+mod folding;
+mod formatting;
+mod guard;
+mod inlay_hints;
+mod links;
+mod logging;
 mod parser;
-mod parser_utils;
-
-// an expression node in the AST
-#[derive(Debug)]
-pub enum Expr {}
-impl Expr {}
-
-pub type Span = Range<usize>;
-pub type Spanned<T> = (T, Span);
-pub type Ast = Vec<Spanned<Expr>>;
+mod progress;
+mod references;
+mod rename;
+mod schema;
+mod schemes;
+mod selection;
+mod semantic_tokens;
+mod signature_help;
+mod symbols;
 
 #[derive(Debug, Deserialize, Serialize)]
 struct NotificationParams {
@@ -34,139 +54,1279 @@ impl Notification for CNotification {
     const METHOD: &'static str = "custom/notification";
 }
 
-#[derive(Debug)]
+/// How long to wait before retrying the analysis of a document that failed.
+const REANALYSIS_DELAY: Duration = Duration::from_secs(2);
+
+/// How many times a failed analysis is retried before waiting for the document to change.
+const REANALYSIS_ATTEMPTS: u32 = 3;
+
+/// Command re-reading every case from disk.
+const REINDEX_COMMAND: &str = "ofoam_ls.reindex";
+
+/// Registration ids of the file watchers, so that they can be replaced later.
+const CASE_WATCHER_ID: &str = "ofoam_ls/watch-case-files";
+const INCLUDE_WATCHER_ID: &str = "ofoam_ls/watch-includes";
+
+/// Registration id of the `workspace/didChangeConfiguration` notification.
+const CONFIGURATION_WATCHER_ID: &str = "ofoam_ls/watch-configuration";
+
+/// Globs covering the files `Case::files_to_index` reads. Time directories are matched by
+/// their leading digit, which also catches `0.orig`.
+const CASE_FILE_GLOBS: &[&str] = &["**/system/**", "**/constant/**", "**/[0-9]*/*"];
+
+/// Most symbols returned for a workspace symbol query, which editors repeat on every keystroke.
+const MAX_WORKSPACE_SYMBOLS: usize = 500;
+
+#[derive(Debug, Clone)]
 struct Backend {
     client: Client,
-    ast_map: HashMap<String, Ast>,
+    documents: Arc<DashMap<Url, Document>>,
+    /// What each open file is, `None` for files that are not OpenFOAM files. OpenFOAM files are
+    /// classified when opened and again when the cases' includes change, not on every edit;
+    /// other files on every edit, until they gain a header or a case includes them.
+    kinds: Arc<DashMap<Url, Option<FileKind>>>,
+    /// Indexed cases by root directory
+    cases: Arc<DashMap<PathBuf, Case>>,
+    workspace_roots: Arc<RwLock<Vec<PathBuf>>>,
+    /// Whether the client accepts snippets in completion items
+    snippet_support: Arc<AtomicBool>,
+    /// Whether the client applies workspace edits that rename files
+    file_renames: Arc<AtomicBool>,
+    /// Whether the client lets us register `workspace/didChangeWatchedFiles` watchers
+    can_watch_files: Arc<AtomicBool>,
+    /// Whether the client answers `workspace/configuration` requests
+    configuration_support: Arc<AtomicBool>,
+    /// Whether the client lets us register for `workspace/didChangeConfiguration`
+    can_watch_configuration: Arc<AtomicBool>,
+    /// Formatting options by workspace folder, `None` for files outside every folder. Read
+    /// from the client on first use and dropped when its configuration changes.
+    format_settings: Arc<DashMap<Option<PathBuf>, FormatOptions>>,
+    /// Files outside the case layouts currently watched because a case includes them
+    watched_includes: Arc<Mutex<Vec<PathBuf>>>,
+    progress: Arc<ProgressTracker>,
+    semantic_tokens: Arc<TokenCache>,
+    trace: Arc<logging::LspTrace>,
 }
 
 impl Backend {
-    
+    #[tracing::instrument(skip_all, fields(value = ?params.value))]
+    async fn set_trace(&self, params: SetTraceParams) {
+        self.trace.set(params.value);
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.uri, version = params.version))]
     async fn on_change(&self, params: TextDocumentItem) {
-        let text = params.text;
-
-        let (_, (tokens, spans)) = parser::scan(&text).unwrap();
-
-        let errors = parser::get_errors(&tokens, &spans);
-
-        // let diagnostics =  errors
-        //     .into_iter()
-        //     .map(|error| {
-        //         Diagnostic {
-        //             range: Range {
-        //                 start: Position {
-        //                     line: error.line as u32,
-        //                     character: error.col as u32,
-        //                 },
-        //                 end: Position {
-        //                     line: error.line as u32,
-        //                     character: error.col as u32 + 1,
-        //                 },
-        //             },
-        //             severity: Some(DiagnosticSeverity::ERROR),
-        //             code: None,
-        //             source: Some("Foam Language Server".to_string()),
-        //             message: error.message,
-        //             ..Diagnostic::default()
-        //         }
-        //     })
-        //     .collect::<Vec<_>>();
+        // Files that are not OpenFOAM files yet may become one with this edit
+        let cached = self.kinds.get(&params.uri).and_then(|kind| *kind);
+        let kind = cached.or_else(|| {
+            let kind = self.file_kind(&params.uri, &params.text);
+            self.kinds.insert(params.uri.clone(), kind);
+            kind
+        });
+        let Some(kind) = kind else {
+            tracing::debug!("not an OpenFOAM file");
+            if self.documents.remove(&params.uri).is_some() {
+                self.client
+                    .publish_diagnostics(params.uri, Vec::new(), None)
+                    .await;
+            }
+            return;
+        };
+
+        let document = Document::new(params.text, params.version, kind);
+        let diagnostics = document.diagnostics();
+        let failed = document.health != Health::Ok;
+        self.documents.insert(params.uri.clone(), document);
+
+        self.client
+            .publish_diagnostics(params.uri.clone(), diagnostics, Some(params.version))
+            .await;
+
+        if failed {
+            self.schedule_reanalysis(params.uri, params.version);
+        }
+    }
+
+    /// What kind of OpenFOAM file `uri` is, or `None` when it is some other file. Files that
+    /// a case includes are dictionaries whatever they look like.
+    fn file_kind(&self, uri: &Url, text: &str) -> Option<FileKind> {
+        let path = uri
+            .to_file_path()
+            .unwrap_or_else(|_| PathBuf::from(uri.path()));
+        FileKind::detect(&path, text).or_else(|| {
+            self.cases
+                .iter()
+                .any(|case| case.is_included(&path))
+                .then_some(FileKind::Dictionary)
+        })
+    }
+
+    /// Classify the open documents again after the cases' includes changed, re-analysing
+    /// those whose kind changed. Files that were not OpenFOAM files are classified again on
+    /// their next edit anyway.
+    async fn reclassify_documents(&self) {
+        let mut changed = Vec::new();
+        for mut entry in self.documents.iter_mut() {
+            let uri = entry.key().clone();
+            let kind = self.file_kind(&uri, &entry.text);
+            self.kinds.insert(uri.clone(), kind);
+            match kind {
+                Some(kind) if kind == entry.kind => {}
+                Some(kind) => {
+                    entry.kind = kind;
+                    entry.analyze();
+                    changed.push((uri, Some(entry.diagnostics()), entry.version));
+                }
+                None => changed.push((uri, None, entry.version)),
+            }
+        }
+
+        for (uri, diagnostics, version) in changed {
+            tracing::debug!(%uri, "reclassified");
+            match diagnostics {
+                Some(diagnostics) => {
+                    self.client
+                        .publish_diagnostics(uri, diagnostics, Some(version))
+                        .await
+                }
+                None => {
+                    self.documents.remove(&uri);
+                    self.client.publish_diagnostics(uri, Vec::new(), None).await
+                }
+            }
+        }
+    }
+
+    /// Retry a failed analysis after a delay, waiting longer each time it fails again. Gives up
+    /// after a few attempts, when it succeeds, or when the document changes or is closed; the
+    /// next edit analyses it afresh.
+    fn schedule_reanalysis(&self, uri: Url, version: i32) {
+        let backend = self.clone();
+
+        tokio::spawn(async move {
+            let mut delay = REANALYSIS_DELAY;
+            for _ in 0..REANALYSIS_ATTEMPTS {
+                tokio::time::sleep(delay).await;
+
+                let (diagnostics, failed) = {
+                    let Some(mut document) = backend.documents.get_mut(&uri) else {
+                        return;
+                    };
+                    if document.version != version {
+                        return;
+                    }
+                    document.analyze();
+                    (document.diagnostics(), document.health != Health::Ok)
+                };
+
+                tracing::info!(%uri, version, failed, "re-analysed document");
+                backend
+                    .client
+                    .publish_diagnostics(uri.clone(), diagnostics, Some(version))
+                    .await;
+                if !failed {
+                    return;
+                }
+                delay *= 2;
+            }
+        });
+    }
+
+    async fn cancel_progress(&self, params: WorkDoneProgressCancelParams) {
+        self.progress.cancel(&params.token);
+    }
+
+    /// The cases in the given workspace folders: those found below each folder, or the case a
+    /// folder is itself inside.
+    fn discover_cases(folders: &[PathBuf]) -> Vec<PathBuf> {
+        let mut roots = Vec::new();
+        for folder in folders {
+            match Case::root_of(folder) {
+                Some(root) => roots.push(root),
+                None => roots.extend(Case::discover(folder)),
+            }
+        }
+        roots.sort();
+        roots.dedup();
+        roots
+    }
+
+    /// Read the given cases from disk in the background, reporting progress to the client.
+    fn spawn_indexing(&self, roots: Vec<PathBuf>) {
+        let backend = self.clone();
+        tokio::spawn(async move { guard::notification("index", backend.index_cases(roots)).await });
+    }
+
+    /// The formatting options of the project holding `uri`, from its `ofoam_ls.format`
+    /// settings, or the defaults when the client has none. Asked once per workspace folder.
+    async fn format_options(&self, uri: &Url) -> FormatOptions {
+        if !self.configuration_support.load(Ordering::Relaxed) {
+            return FormatOptions::default();
+        }
+        let folder = self.workspace_folder_of(uri);
+        if let Some(options) = self.format_settings.get(&folder) {
+            return options.clone();
+        }
+
+        let scope_uri = folder
+            .as_deref()
+            .and_then(|folder| Url::from_directory_path(folder).ok())
+            .unwrap_or_else(|| uri.clone());
+        let options = self.read_format_options(scope_uri).await;
+        self.format_settings.insert(folder, options.clone());
+        options
+    }
+
+    async fn read_format_options(&self, scope_uri: Url) -> FormatOptions {
+        let item = ConfigurationItem {
+            scope_uri: Some(scope_uri),
+            section: Some("ofoam_ls.format".to_string()),
+        };
+        let settings = match self.client.configuration(vec![item]).await {
+            Ok(mut settings) if !settings.is_empty() => settings.swap_remove(0),
+            Ok(_) => return FormatOptions::default(),
+            Err(error) => {
+                tracing::warn!(%error, "could not read the formatting settings");
+                return FormatOptions::default();
+            }
+        };
+        if settings.is_null() {
+            return FormatOptions::default();
+        }
+        serde_json::from_value(settings).unwrap_or_else(|error| {
+            tracing::warn!(%error, "invalid formatting settings");
+            FormatOptions::default()
+        })
+    }
+
+    /// The innermost workspace folder holding `uri`.
+    fn workspace_folder_of(&self, uri: &Url) -> Option<PathBuf> {
+        let path = uri.to_file_path().ok()?;
+        self.workspace_roots
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|folder| path.starts_with(folder))
+            .max_by_key(|folder| folder.components().count())
+            .cloned()
+    }
+
+    /// Re-read every case in the workspace.
+    fn reindex_workspace(&self) {
+        let folders = self.workspace_roots.read().unwrap().clone();
+        let roots = Self::discover_cases(&folders);
+        self.cases.retain(|root, _| roots.contains(root));
+        self.spawn_indexing(roots);
+    }
+
+    #[tracing::instrument(skip_all, fields(cases = roots.len()))]
+    async fn index_cases(&self, roots: Vec<PathBuf>) {
+        let title = match roots.as_slice() {
+            [] => return,
+            [root] => format!("Indexing {}", root.display()),
+            _ => format!("Indexing {} cases", roots.len()),
+        };
+        // Claim the cases up front so that opening one of their files does not index them twice
+        let claimed: Vec<_> = roots
+            .iter()
+            .filter(|root| !self.cases.contains_key(*root))
+            .cloned()
+            .collect();
+        for root in &claimed {
+            self.cases.insert(root.clone(), Case::new(root.clone()));
+        }
+
+        let mut progress = self.progress.begin(&self.client, &title).await;
+
+        let files: Vec<_> = roots
+            .iter()
+            .flat_map(|root| {
+                Case::files_to_index(root)
+                    .into_iter()
+                    .map(move |path| (root.clone(), path))
+            })
+            .collect();
+
+        let mut cases: Vec<_> = roots.iter().cloned().map(Case::new).collect();
+        let mut indexed = 0;
+        for (root, path) in &files {
+            if progress.is_cancelled() {
+                tracing::info!(indexed, total = files.len(), "indexing cancelled");
+                break;
+            }
+
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            progress
+                .report(indexed, files.len(), relative.display().to_string())
+                .await;
+            indexed += 1;
+
+            let load_path = path.clone();
+            let Ok(Some(document)) =
+                tokio::task::spawn_blocking(move || Case::load(&load_path)).await
+            else {
+                continue;
+            };
+            if let Some(case) = cases.iter_mut().find(|case| &case.root == root) {
+                case.insert(path.clone(), document);
+            }
+        }
+
+        // A half-read case would hide the files that were not reached, so a cancellation
+        // keeps the cases as they were and releases the claims for a later indexing
+        if progress.is_cancelled() {
+            for root in &claimed {
+                self.cases.remove(root);
+            }
+            let message = format!("Cancelled after {indexed} of {} files", files.len());
+            progress.end(message).await;
+            return;
+        }
+
+        for mut case in cases {
+            case.time_dirs = Case::initial_time_dirs(&case.root);
+            case.load_includes();
+            tracing::info!(case = %case.root.display(), files = case.files.len(), "indexed case");
+            self.cases.insert(case.root.clone(), case);
+        }
+        progress.end(format!("Indexed {} files", files.len())).await;
+
+        self.reclassify_documents().await;
+        self.watch_includes().await;
+    }
+
+    /// Read the time directory holding `path` into the case at `root` when indexing skipped
+    /// it, so that the fields of an opened result are known to the rest of the case.
+    async fn load_time_dir(&self, root: &Path, path: &Path) {
+        let Some(dir) = self
+            .cases
+            .get(root)
+            .and_then(|case| case.unread_time_dir(path))
+        else {
+            return;
+        };
+
+        let read_dir = dir.clone();
+        let Ok(documents) = tokio::task::spawn_blocking(move || {
+            Case::time_dir_files(&read_dir)
+                .into_iter()
+                .filter_map(|path| Some((path.clone(), Case::load(&path)?)))
+                .collect::<Vec<_>>()
+        })
+        .await
+        else {
+            return;
+        };
+
+        if let Some(mut case) = self.cases.get_mut(root)
+            && !case.time_dirs.contains(&dir)
+        {
+            tracing::debug!(dir = %dir.display(), files = documents.len(), "read time directory");
+            case.time_dirs.push(dir);
+            for (path, document) in documents {
+                case.insert(path, document);
+            }
+        }
+    }
+
+    /// Ask the client to tell us about changes to case files on disk.
+    async fn watch_case_files(&self) {
+        if !self.can_watch_files.load(Ordering::Relaxed) {
+            return;
+        }
+        let globs = CASE_FILE_GLOBS
+            .iter()
+            .map(|glob| glob.to_string())
+            .collect();
+        self.register_watcher(CASE_WATCHER_ID, globs).await;
+    }
+
+    /// Keep the watcher on included files outside the case layouts in step with the index.
+    async fn watch_includes(&self) {
+        if !self.can_watch_files.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let mut includes: Vec<_> = self
+            .cases
+            .iter()
+            .flat_map(|case| case.external_includes())
+            .collect();
+        includes.sort();
+        includes.dedup();
+
+        let previous = {
+            let mut watched = self.watched_includes.lock().unwrap();
+            if *watched == includes {
+                return;
+            }
+            std::mem::replace(&mut *watched, includes.clone())
+        };
+
+        if !previous.is_empty() {
+            let unregistered = self
+                .client
+                .unregister_capability(vec![Unregistration {
+                    id: INCLUDE_WATCHER_ID.to_string(),
+                    method: "workspace/didChangeWatchedFiles".to_string(),
+                }])
+                .await;
+            if let Err(error) = unregistered {
+                tracing::warn!(%error, "could not unregister include watcher");
+            }
+        }
+        if !includes.is_empty() {
+            let globs = includes
+                .iter()
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect();
+            self.register_watcher(INCLUDE_WATCHER_ID, globs).await;
+        }
+    }
+
+    /// Ask to be told when the settings change, so that cached formatting options are re-read.
+    async fn watch_configuration(&self) {
+        if !self.configuration_support.load(Ordering::Relaxed)
+            || !self.can_watch_configuration.load(Ordering::Relaxed)
+        {
+            return;
+        }
+        let registered = self
+            .client
+            .register_capability(vec![Registration {
+                id: CONFIGURATION_WATCHER_ID.to_string(),
+                method: "workspace/didChangeConfiguration".to_string(),
+                register_options: Some(serde_json::json!({ "section": "ofoam_ls" })),
+            }])
+            .await;
+        if let Err(error) = registered {
+            tracing::warn!(%error, "could not register for configuration changes");
+        }
+    }
+
+    async fn register_watcher(&self, id: &str, globs: Vec<String>) {
+        let watchers = globs
+            .into_iter()
+            .map(|glob| FileSystemWatcher {
+                glob_pattern: GlobPattern::String(glob),
+                kind: None,
+            })
+            .collect();
+        let options = DidChangeWatchedFilesRegistrationOptions { watchers };
+
+        let registered = self
+            .client
+            .register_capability(vec![Registration {
+                id: id.to_string(),
+                method: "workspace/didChangeWatchedFiles".to_string(),
+                register_options: serde_json::to_value(options).ok(),
+            }])
+            .await;
+        match registered {
+            Ok(()) => tracing::debug!(id, "registered file watcher"),
+            Err(error) => tracing::warn!(id, %error, "could not register file watcher"),
+        }
+    }
+
+    /// Bring the index up to date with files changed on disk and refresh whatever depends
+    /// on them.
+    #[tracing::instrument(skip_all, fields(changes = changes.len()))]
+    async fn on_watched_files_changed(&self, changes: Vec<FileEvent>) {
+        let mut new_cases = Vec::new();
+        let mut affected = Vec::new();
+
+        for change in changes {
+            let Ok(path) = change.uri.to_file_path() else {
+                continue;
+            };
+            tracing::debug!(path = %path.display(), kind = ?change.typ, "watched file changed");
+
+            // A controlDict appearing or disappearing creates or removes a whole case
+            if path.ends_with("system/controlDict")
+                && let Some(root) = path.parent().and_then(Path::parent)
+            {
+                if change.typ == FileChangeType::DELETED {
+                    self.cases.remove(root);
+                    continue;
+                }
+                if !self.cases.contains_key(root) && self.in_workspace(root) {
+                    new_cases.push(root.to_path_buf());
+                    continue;
+                }
+            }
+
+            for mut case in self.cases.iter_mut() {
+                let owned = path.starts_with(&case.root);
+                if !owned && !case.is_included(&path) {
+                    continue;
+                }
+                case.reload(&path);
+                affected.extend(case.dependents(&path));
+                affected.push(path.clone());
+            }
+        }
+
+        affected.sort();
+        affected.dedup();
+        self.reclassify_documents().await;
+        self.refresh_documents(&affected).await;
+        self.watch_includes().await;
+        self.spawn_indexing(new_cases);
+    }
 
+    fn in_workspace(&self, path: &Path) -> bool {
+        let folders = self.workspace_roots.read().unwrap();
+        folders.iter().any(|folder| path.starts_with(folder))
+    }
+
+    /// Re-analyse the open documents among `paths` and republish their diagnostics.
+    async fn refresh_documents(&self, paths: &[PathBuf]) {
+        let mut refreshed = Vec::new();
+        for mut entry in self.documents.iter_mut() {
+            let open = entry
+                .key()
+                .to_file_path()
+                .is_ok_and(|path| paths.contains(&path));
+            if open {
+                entry.analyze();
+                refreshed.push((entry.key().clone(), entry.diagnostics(), entry.version));
+            }
+        }
+
+        for (uri, diagnostics, version) in refreshed {
+            tracing::debug!(%uri, "refreshed after a change on disk");
+            self.client
+                .publish_diagnostics(uri, diagnostics, Some(version))
+                .await;
+        }
+    }
+
+    /// Run `f` on the open document at `uri`, or on the file read from disk if it is not open.
+    fn with_document<T>(&self, uri: &Url, f: impl FnOnce(&Document) -> T) -> Option<T> {
+        if let Some(document) = self.documents.get(uri) {
+            return Some(f(&document));
+        }
+
+        let path = uri.to_file_path().ok()?;
+        let text = std::fs::read_to_string(path).ok()?;
+        let kind = self.file_kind(uri, &text)?;
+        Some(f(&Document::new(text, 0, kind)))
+    }
+
+    /// Run `f` on the case the document at `uri` belongs to, if it has been indexed.
+    fn with_case<T>(&self, uri: &Url, f: impl FnOnce(Option<&Case>) -> T) -> T {
+        let case = uri
+            .to_file_path()
+            .ok()
+            .and_then(|path| Case::root_of(&path))
+            .and_then(|root| self.cases.get(&root));
+        f(case.as_deref())
+    }
+
+    /// Run `f` on the case the document at `uri` belongs to and on its files, taking open
+    /// documents over what is on disk.
+    fn with_case_files<T>(
+        &self,
+        uri: &Url,
+        f: impl FnOnce(&Case, &[(&Path, &Document)]) -> T,
+    ) -> Option<T> {
+        let root = Case::root_of(&uri.to_file_path().ok()?)?;
+        let case = self.cases.get(&root)?;
+        let open: Vec<_> = self
+            .documents
+            .iter()
+            .filter_map(|entry| Some((entry.key().to_file_path().ok()?, entry)))
+            .filter(|(path, _)| case.files.contains_key(path) || case.is_case_file(path))
+            .collect();
+
+        let mut files: Vec<(&Path, &Document)> = case
+            .files
+            .iter()
+            .filter(|(path, _)| !open.iter().any(|(open, _)| open == *path))
+            .map(|(path, document)| (path.as_path(), document))
+            .collect();
+        files.extend(
+            open.iter()
+                .map(|(path, entry)| (path.as_path(), entry.value())),
+        );
+        Some(f(&case, &files))
     }
 }
 
 #[async_trait]
 impl LanguageServer for Backend {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    #[tracing::instrument(skip_all)]
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        if let Some(value) = params.trace {
+            self.trace.set(value);
+        }
+
+        self.progress.set_supported(
+            params
+                .capabilities
+                .window
+                .as_ref()
+                .and_then(|window| window.work_done_progress)
+                .unwrap_or(false),
+        );
+
+        #[allow(deprecated)]
+        let roots: Vec<PathBuf> = match (params.workspace_folders, params.root_uri) {
+            (Some(folders), _) => folders
+                .iter()
+                .filter_map(|folder| folder.uri.to_file_path().ok())
+                .collect(),
+            (None, Some(root)) => root.to_file_path().into_iter().collect(),
+            (None, None) => Vec::new(),
+        };
+        *self.workspace_roots.write().unwrap() = roots;
+
+        self.snippet_support.store(
+            params
+                .capabilities
+                .text_document
+                .as_ref()
+                .and_then(|text_document| text_document.completion.as_ref())
+                .and_then(|completion| completion.completion_item.as_ref())
+                .and_then(|item| item.snippet_support)
+                .unwrap_or(false),
+            Ordering::Relaxed,
+        );
+        let workspace_edit = params
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.workspace_edit.as_ref());
+        self.file_renames.store(
+            workspace_edit.is_some_and(|edit| {
+                edit.document_changes == Some(true)
+                    && edit.resource_operations.as_ref().is_some_and(|operations| {
+                        operations.contains(&ResourceOperationKind::Rename)
+                    })
+            }),
+            Ordering::Relaxed,
+        );
+        self.can_watch_files.store(
+            params
+                .capabilities
+                .workspace
+                .as_ref()
+                .and_then(|workspace| workspace.did_change_watched_files.as_ref())
+                .and_then(|watched| watched.dynamic_registration)
+                .unwrap_or(false),
+            Ordering::Relaxed,
+        );
+        self.configuration_support.store(
+            params
+                .capabilities
+                .workspace
+                .as_ref()
+                .and_then(|workspace| workspace.configuration)
+                .unwrap_or(false),
+            Ordering::Relaxed,
+        );
+        self.can_watch_configuration.store(
+            params
+                .capabilities
+                .workspace
+                .as_ref()
+                .and_then(|workspace| workspace.did_change_configuration.as_ref())
+                .and_then(|configuration| configuration.dynamic_registration)
+                .unwrap_or(false),
+            Ordering::Relaxed,
+        );
+
         Ok(InitializeResult {
             server_info: None,
             offset_encoding: None,
             capabilities: ServerCapabilities {
                 execute_command_provider: Some(ExecuteCommandOptions {
-                    commands: vec!["custom/notifcation".to_string()],
+                    commands: vec![
+                        "custom/notifcation".to_string(),
+                        REINDEX_COMMAND.to_string(),
+                    ],
                     work_done_progress_options: Default::default(),
                 }),
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::FULL,
+                )),
                 hover_provider: Some(HoverProviderCapability::Simple(true)),
+                completion_provider: Some(CompletionOptions::default()),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                definition_provider: Some(OneOf::Left(true)),
+                references_provider: Some(OneOf::Left(true)),
+                rename_provider: Some(OneOf::Right(RenameOptions {
+                    prepare_provider: Some(true),
+                    work_done_progress_options: WorkDoneProgressOptions::default(),
+                })),
+                document_formatting_provider: Some(OneOf::Left(true)),
+                document_range_formatting_provider: Some(OneOf::Left(true)),
+                document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+                    first_trigger_character: ";".to_string(),
+                    more_trigger_character: Some(vec!["}".to_string()]),
+                }),
+                document_link_provider: Some(DocumentLinkOptions {
+                    resolve_provider: Some(false),
+                    work_done_progress_options: WorkDoneProgressOptions::default(),
+                }),
+                folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+                selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+                workspace_symbol_provider: Some(OneOf::Left(true)),
+                signature_help_provider: Some(SignatureHelpOptions {
+                    trigger_characters: Some(vec![" ".to_string()]),
+                    retrigger_characters: None,
+                    work_done_progress_options: Default::default(),
+                }),
+                inlay_hint_provider: Some(OneOf::Right(InlayHintServerCapabilities::Options(
+                    InlayHintOptions {
+                        resolve_provider: Some(true),
+                        work_done_progress_options: Default::default(),
+                    },
+                ))),
+                semantic_tokens_provider: Some(
+                    SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        SemanticTokensOptions {
+                            legend: semantic_tokens::legend(),
+                            range: Some(true),
+                            full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                            work_done_progress_options: Default::default(),
+                        },
+                    ),
+                ),
+                workspace: Some(WorkspaceServerCapabilities {
+                    workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                        supported: Some(true),
+                        change_notifications: Some(OneOf::Left(true)),
+                    }),
+                    file_operations: None,
+                }),
                 ..ServerCapabilities::default()
             },
         })
     }
 
-    async fn initialized(&self, params: InitializedParams) {
-        self.client.log_message(MessageType::INFO, "...").await;
+    async fn initialized(&self, _: InitializedParams) {
+        if let Some(mut receiver) = self.trace.take_receiver() {
+            let client = self.client.clone();
+            tokio::spawn(async move {
+                while let Some(params) = receiver.recv().await {
+                    client.send_notification::<LogTrace>(params).await;
+                }
+            });
+        }
+        tracing::info!("server initialized");
+
+        self.watch_case_files().await;
+        self.watch_configuration().await;
+        self.reindex_workspace();
     }
 
     async fn shutdown(&self) -> Result<()> {
         Ok(())
     }
 
-    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
-        let pos = params.text_document_position_params;
-        let file = pos.text_document.uri.path();
-        self.client.log_message(MessageType::INFO, file).await;
+    async fn did_change_workspace_folders(&self, params: DidChangeWorkspaceFoldersParams) {
+        let to_paths = |folders: Vec<WorkspaceFolder>| -> Vec<PathBuf> {
+            folders
+                .iter()
+                .filter_map(|folder| folder.uri.to_file_path().ok())
+                .collect()
+        };
+        let added = to_paths(params.event.added);
+        let removed = to_paths(params.event.removed);
 
+        let remaining = {
+            let mut folders = self.workspace_roots.write().unwrap();
+            folders.retain(|folder| !removed.contains(folder));
+            folders.extend(added.iter().cloned());
+            folders.clone()
+        };
 
-        let buffer =  std::fs::read_to_string(&PathBuf::from(file)).ok().unwrap();
+        // Forget cases that were only reachable through a removed folder
+        let still_present = Self::discover_cases(&remaining);
+        self.cases.retain(|root, _| still_present.contains(root));
+        self.reclassify_documents().await;
 
-        let (_, (tokens, spans)) = parser::scan(&buffer).unwrap();
+        let new_cases: Vec<_> = Self::discover_cases(&added)
+            .into_iter()
+            .filter(|root| !self.cases.contains_key(root))
+            .collect();
+        tracing::info!(
+            added = added.len(),
+            removed = removed.len(),
+            new_cases = new_cases.len(),
+            "workspace folders changed"
+        );
+        self.spawn_indexing(new_cases);
+    }
 
-        let chars_per_line = parser::count_characters_per_line(&buffer);
-        let index = parser::index_from_line_and_col(chars_per_line.clone(), pos.position.line as usize, pos.position.character as usize);
+    async fn did_change_configuration(&self, _: DidChangeConfigurationParams) {
+        tracing::debug!("configuration changed");
+        self.format_settings.clear();
+    }
 
-        let mut span_index = 0;
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        guard::notification(
+            "workspace/didChangeWatchedFiles",
+            self.on_watched_files_changed(params.changes),
+        )
+        .await
+    }
 
-        // iterate through spans until index sits between start and end
-        for (i, span) in spans.iter().enumerate() {
-            if span.start <= index && index < span.end {
-                span_index = i;
-                break;
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        if let Ok(path) = params.text_document.uri.to_file_path()
+            && let Some(root) = Case::root_of(&path)
+        {
+            if self.cases.contains_key(&root) {
+                self.load_time_dir(&root, &path).await;
+            } else {
+                // Files opened from outside the workspace bring their case with them
+                self.spawn_indexing(vec![root]);
             }
         }
+        self.kinds.remove(&params.text_document.uri);
 
-        let hover_text = parser::get_foam_definition(tokens[span_index]);
+        guard::notification("textDocument/didOpen", self.on_change(params.text_document)).await
+    }
 
-        self.client.log_message(MessageType::INFO, pos.position.line).await;
-        self.client.log_message(MessageType::INFO, hover_text.clone()).await;
+    async fn did_change(&self, mut params: DidChangeTextDocumentParams) {
+        // Full sync: the last change holds the whole text
+        let Some(change) = params.content_changes.pop() else {
+            return;
+        };
 
-        Ok(Some(Hover {
-            contents: HoverContents::Scalar(MarkedString::LanguageString(LanguageString {
-                language: "".to_string(),
-                value: hover_text.to_string(),
-            })),
-            range: None
-        }))
+        guard::notification(
+            "textDocument/didChange",
+            self.on_change(TextDocumentItem {
+                uri: params.text_document.uri,
+                language_id: String::new(),
+                version: params.text_document.version,
+                text: change.text,
+            }),
+        )
+        .await
     }
 
-    async fn execute_command(&self, params: ExecuteCommandParams) -> Result<Option<Value>> {
-        match params.command.as_str() {
-            "custom/notification" => {
-                self.client
-                    .send_notification::<CNotification>(NotificationParams {
-                        title: "notify".to_string(),
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        self.documents.remove(&params.text_document.uri);
+        self.kinds.remove(&params.text_document.uri);
+        self.semantic_tokens.forget(&params.text_document.uri);
+        self.client
+            .publish_diagnostics(params.text_document.uri, Vec::new(), None)
+            .await;
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document_position_params.text_document.uri))]
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        guard::request("textDocument/hover", async move {
+            let pos = params.text_document_position_params;
+
+            let hover = self.with_document(&pos.text_document.uri, |document| {
+                let index = document.offset_at(pos.position);
+                let (token, span) = document.token_at(index)?;
+
+                tracing::debug!(
+                    line = pos.position.line,
+                    character = pos.position.character,
+                    ?token,
+                    "hover"
+                );
+
+                let hover_text = parser::get_foam_definition(token);
+
+                Some(Hover {
+                    contents: HoverContents::Scalar(MarkedString::LanguageString(LanguageString {
+                        language: "".to_string(),
+                        value: hover_text,
+                    })),
+                    range: Some(document.range_of(span)),
+                })
+            });
+
+            Ok(hover.flatten())
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document.uri))]
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> Result<Option<SemanticTokensResult>> {
+        guard::request("textDocument/semanticTokens/full", async move {
+            let uri = params.text_document.uri;
+            let Some(data) = self.with_document(&uri, semantic_tokens::full) else {
+                return Ok(None);
+            };
+            let tokens = self.semantic_tokens.store(uri, data);
+            Ok(Some(SemanticTokensResult::Tokens(tokens)))
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document.uri))]
+    async fn semantic_tokens_full_delta(
+        &self,
+        params: SemanticTokensDeltaParams,
+    ) -> Result<Option<SemanticTokensFullDeltaResult>> {
+        guard::request("textDocument/semanticTokens/full/delta", async move {
+            let uri = params.text_document.uri;
+            let Some(data) = self.with_document(&uri, semantic_tokens::full) else {
+                return Ok(None);
+            };
+            let previous = self
+                .semantic_tokens
+                .previous(&uri, &params.previous_result_id);
+            let tokens = self.semantic_tokens.store(uri, data);
+
+            // Without the result the client diffs against, all we can do is send everything
+            let Some(previous) = previous else {
+                return Ok(Some(SemanticTokensFullDeltaResult::Tokens(tokens)));
+            };
+            Ok(Some(SemanticTokensFullDeltaResult::TokensDelta(
+                SemanticTokensDelta {
+                    result_id: tokens.result_id,
+                    edits: semantic_tokens::diff(&previous, &tokens.data),
+                },
+            )))
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document.uri))]
+    async fn semantic_tokens_range(
+        &self,
+        params: SemanticTokensRangeParams,
+    ) -> Result<Option<SemanticTokensRangeResult>> {
+        guard::request("textDocument/semanticTokens/range", async move {
+            let data = self.with_document(&params.text_document.uri, |document| {
+                semantic_tokens::range(document, params.range)
+            });
+            Ok(data.map(|data| {
+                SemanticTokensRangeResult::Tokens(SemanticTokens {
+                    result_id: None,
+                    data,
+                })
+            }))
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document_position.text_document.uri))]
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        guard::request("textDocument/completion", async move {
+            let position = params.text_document_position;
+            let snippets = self.snippet_support.load(Ordering::Relaxed);
+            let uri = &position.text_document.uri;
+            let options = self.format_options(uri).await;
+            let items = self.with_document(uri, |document| {
+                self.with_case(uri, |case| {
+                    completion::complete(document, position.position, snippets, case, &options)
+                })
+            });
+            Ok(items.map(CompletionResponse::Array))
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document_position_params.text_document.uri))]
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        guard::request("textDocument/definition", async move {
+            let position = params.text_document_position_params;
+            let uri = &position.text_document.uri;
+            let Ok(path) = uri.to_file_path() else {
+                return Ok(None);
+            };
+            let locations = self.with_document(uri, |document| {
+                self.with_case(uri, |case| {
+                    definition::definition(document, &path, position.position, case)
+                })
+            });
+            Ok(locations
+                .filter(|locations| !locations.is_empty())
+                .map(GotoDefinitionResponse::Array))
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document_position.text_document.uri))]
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        guard::request("textDocument/references", async move {
+            let position = params.text_document_position;
+            let uri = &position.text_document.uri;
+            let locations = self.with_case_files(uri, |case, files| {
+                let (path, document) = files
+                    .iter()
+                    .find(|(path, _)| Url::from_file_path(path).as_ref() == Ok(uri))?;
+                let offset = document.offset_at(position.position);
+                let entity = references::entity_at(document, path, offset, case)?;
+                let locations = references::references(files, case, entity.entity, entity.name)
+                    .into_iter()
+                    .filter(|(_, mention)| {
+                        params.context.include_declaration || !mention.declaration
                     })
-                    .await;
+                    .filter_map(|(path, mention)| {
+                        let document = files.iter().find(|(file, _)| *file == path)?.1;
+                        Some(Location::new(
+                            Url::from_file_path(path).ok()?,
+                            document.range_of(mention.span),
+                        ))
+                    })
+                    .collect();
+                Some(locations)
+            });
+            Ok(locations.flatten())
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document.uri))]
+    async fn prepare_rename(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Option<PrepareRenameResponse>> {
+        guard::request("textDocument/prepareRename", async move {
+            let uri = &params.text_document.uri;
+            let Ok(path) = uri.to_file_path() else {
+                return Ok(None);
+            };
+            let range = self.with_document(uri, |document| {
+                self.with_case(uri, |case| {
+                    let offset = document.offset_at(params.position);
+                    let mention = rename::prepare(document, &path, offset, case?)?;
+                    Some(document.range_of(mention.span))
+                })
+            });
+            Ok(range.flatten().map(PrepareRenameResponse::Range))
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document_position.text_document.uri))]
+    async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        guard::request("textDocument/rename", async move {
+            let position = params.text_document_position;
+            let uri = &position.text_document.uri;
+            let Ok(path) = uri.to_file_path() else {
+                return Ok(None);
+            };
+            let rename = self.with_case_files(uri, |case, files| {
+                let (_, document) = files.iter().find(|(file, _)| *file == path)?;
+                let offset = document.offset_at(position.position);
+                let file_operations = self.file_renames.load(Ordering::Relaxed);
+                Some(rename::rename(
+                    files,
+                    &path,
+                    offset,
+                    case,
+                    &params.new_name,
+                    file_operations,
+                ))
+            });
+            let rename = match rename.flatten() {
+                Some(Ok(rename)) => rename,
+                Some(Err(message)) => return Err(Error::invalid_params(message)),
+                None => return Ok(None),
+            };
+            for warning in rename.warnings {
                 self.client
-                    .log_message(MessageType::INFO, String::new())
+                    .show_message(MessageType::WARNING, warning)
                     .await;
             }
-            _ => {}
-        }
-        Ok(None)
+            Ok(Some(rename.edit))
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document.uri))]
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>> {
+        guard::request("textDocument/documentSymbol", async move {
+            Ok(self
+                .with_document(&params.text_document.uri, symbols::document_symbols)
+                .map(DocumentSymbolResponse::Nested))
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document.uri))]
+    async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        guard::request("textDocument/formatting", async move {
+            let uri = &params.text_document.uri;
+            let options = self.format_options(uri).await;
+            Ok(self.with_document(uri, |document| formatting::format(document, &options)))
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document.uri))]
+    async fn range_formatting(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        guard::request("textDocument/rangeFormatting", async move {
+            let uri = &params.text_document.uri;
+            let options = self.format_options(uri).await;
+            Ok(self.with_document(uri, |document| {
+                formatting::format_range(document, params.range, &options)
+            }))
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document_position.text_document.uri))]
+    async fn on_type_formatting(
+        &self,
+        params: DocumentOnTypeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        guard::request("textDocument/onTypeFormatting", async move {
+            let position = params.text_document_position;
+            let uri = &position.text_document.uri;
+            let options = self.format_options(uri).await;
+            Ok(self.with_document(uri, |document| {
+                formatting::format_on_type(document, position.position, &options)
+            }))
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document.uri))]
+    async fn document_link(&self, params: DocumentLinkParams) -> Result<Option<Vec<DocumentLink>>> {
+        guard::request("textDocument/documentLink", async move {
+            let uri = &params.text_document.uri;
+            let Ok(path) = uri.to_file_path() else {
+                return Ok(None);
+            };
+            let etc = links::etc_dirs();
+            Ok(self.with_document(uri, |document| links::document_links(document, &path, &etc)))
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document.uri))]
+    async fn folding_range(&self, params: FoldingRangeParams) -> Result<Option<Vec<FoldingRange>>> {
+        guard::request("textDocument/foldingRange", async move {
+            Ok(self.with_document(&params.text_document.uri, folding::folding_ranges))
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document.uri))]
+    async fn selection_range(
+        &self,
+        params: SelectionRangeParams,
+    ) -> Result<Option<Vec<SelectionRange>>> {
+        guard::request("textDocument/selectionRange", async move {
+            Ok(self.with_document(&params.text_document.uri, |document| {
+                selection::selection_ranges(document, &params.positions)
+            }))
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(query = %params.query))]
+    async fn symbol(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> Result<Option<Vec<SymbolInformation>>> {
+        guard::request("workspace/symbol", async move {
+            let symbols =
+                symbols::workspace_symbols(self.cases.iter(), &params.query, MAX_WORKSPACE_SYMBOLS);
+            Ok(Some(symbols))
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document_position_params.text_document.uri))]
+    async fn signature_help(&self, params: SignatureHelpParams) -> Result<Option<SignatureHelp>> {
+        guard::request("textDocument/signatureHelp", async move {
+            let position = params.text_document_position_params;
+            Ok(self
+                .with_document(&position.text_document.uri, |document| {
+                    signature_help::signature_help(document, position.position)
+                })
+                .flatten())
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(uri = %params.text_document.uri))]
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        guard::request("textDocument/inlayHint", async move {
+            let uri = params.text_document.uri;
+            Ok(self.with_document(&uri, |document| {
+                inlay_hints::hints(document, &uri, params.range)
+            }))
+        })
+        .await
+    }
+
+    async fn inlay_hint_resolve(&self, hint: InlayHint) -> Result<InlayHint> {
+        guard::request("inlayHint/resolve", async move {
+            Ok(inlay_hints::resolve(hint, |uri, tooltip| {
+                self.with_document(uri, tooltip).flatten()
+            }))
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(command = %params.command))]
+    async fn execute_command(&self, params: ExecuteCommandParams) -> Result<Option<Value>> {
+        guard::request("workspace/executeCommand", async move {
+            match params.command.as_str() {
+                "custom/notification" => {
+                    self.client
+                        .send_notification::<CNotification>(NotificationParams {
+                            title: "notify".to_string(),
+                        })
+                        .await;
+                }
+                REINDEX_COMMAND => self.reindex_workspace(),
+                _ => {}
+            }
+            Ok(None)
+        })
+        .await
     }
 }
 
 #[tokio::main]
-async fn main() {
-    // env_logger::init();
+async fn main() -> anyhow::Result<()> {
+    let args = cli::Args::parse();
+    let trace = logging::LspTrace::new();
+    logging::init(args.log_file.as_deref(), trace.clone())?;
+
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), "starting ofoam_ls");
 
-    let stdin = tokio::io::stdin();
-    let stdout = tokio::io::stdout();
+    // Route panic reports through tracing; handlers recover from them (see `guard`)
+    std::panic::set_hook(Box::new(|info| tracing::error!(%info, "panic")));
 
     let (service, socket) = LspService::build(|client| Backend {
         client,
-        ast_map: HashMap::new(),
+        documents: Arc::new(DashMap::new()),
+        kinds: Arc::new(DashMap::new()),
+        cases: Arc::new(DashMap::new()),
+        workspace_roots: Arc::new(RwLock::new(Vec::new())),
+        snippet_support: Arc::new(AtomicBool::new(false)),
+        file_renames: Arc::new(AtomicBool::new(false)),
+        can_watch_files: Arc::new(AtomicBool::new(false)),
+        configuration_support: Arc::new(AtomicBool::new(false)),
+        can_watch_configuration: Arc::new(AtomicBool::new(false)),
+        format_settings: Arc::new(DashMap::new()),
+        watched_includes: Arc::new(Mutex::new(Vec::new())),
+        progress: Arc::new(ProgressTracker::default()),
+        semantic_tokens: Arc::new(TokenCache::default()),
+        trace,
     })
+    .custom_method("$/setTrace", Backend::set_trace)
+    .custom_method("window/workDoneProgress/cancel", Backend::cancel_progress)
     .finish();
 
-    Server::new(stdin, stdout, socket).serve(service).await;
+    args.transport().serve(service, socket).await?;
+    Ok(())
 }
@@ -1,13 +1,14 @@
 use std::collections::HashMap;
 
 use nom::branch::alt;
-use nom::bytes::complete::tag;
-use nom::character::complete::alphanumeric1;
-use nom::number::complete::double;
+use nom::bytes::complete::{tag, take_until, take_while1};
+use nom::character::complete::{alpha1, anychar, line_ending, not_line_ending};
+use nom::combinator::{opt, recognize, rest};
+use nom::number::complete::{double, recognize_float};
 use nom::sequence::delimited;
 use nom::{IResult, Parser};
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
@@ -95,50 +96,53 @@ pub enum Token {
     NoSlip,
     Empty,
 
+    // Anything else; the text is recovered from the token's span
+    Word,
+    String,
+    Macro,
+    Directive,
+    Verbatim,
+    Unknown,
+
     BlockComment,
     LineComment,
-    Eof,
-}
-
-/// Count how many characters there are per line, inluding new lines
-pub fn count_characters_per_line(input: &str) -> Vec<usize> {
-    input
-        .lines()
-        .map(|line| line.len() + 1) // +1 for the newline character
-        .collect()
 }
 
-pub fn index_from_line_and_col(chars_per_line: Vec<usize>, line: usize, col: usize) -> usize {
-    let mut index = 0;
-
-    // Do cumulative sum of characters per line up to the given line
-    for &num_chars in chars_per_line.iter().take(line) {
-        index += num_chars;
-    }
-
-    // Add the column index to the cumulative sum
-    index += col;
-
-    index
-}
-
-pub fn col_from_index(chars_per_line: Vec<usize>, index: usize) -> usize {
-    let mut col = 0;
-
-    let mut cumulative_chars = 0;
-    // Loop through
-    for &num_chars in chars_per_line.iter() {
-        if index >= cumulative_chars && index < cumulative_chars + num_chars {
-            col = index - cumulative_chars;
-            break;
-        }
-        cumulative_chars += num_chars;
+impl Token {
+    /// Whether the token is a word, either one of the keywords above or any other.
+    pub fn is_word(&self) -> bool {
+        !matches!(
+            self,
+            Token::LeftParen
+                | Token::RightParen
+                | Token::LeftBrace
+                | Token::RightBrace
+                | Token::LeftBracket
+                | Token::RightBracket
+                | Token::Comma
+                | Token::Dot
+                | Token::Minus
+                | Token::Plus
+                | Token::Semicolon
+                | Token::Slash
+                | Token::Star
+                | Token::Int(_)
+                | Token::Float(_)
+                | Token::String
+                | Token::Macro
+                | Token::Directive
+                | Token::Verbatim
+                | Token::Unknown
+                | Token::BlockComment
+                | Token::LineComment
+        )
     }
-
-    col
 }
 
-/// Use nom to parse lines of lox code and return a vector of tokens and spans.
+/// Use nom to parse lines of OpenFOAM dictionary and return a vector of tokens and spans.
+///
+/// Scanning never fails: text that is not understood becomes `Token::Word` or
+/// `Token::Unknown`, so a half-typed file still produces a usable token stream.
 pub fn scan(input: &str) -> IResult<&str, (Vec<Token>, Vec<Span>)> {
     let mut tokens = Vec::new();
     let mut spans = Vec::new();
@@ -159,7 +163,19 @@ pub fn scan(input: &str) -> IResult<&str, (Vec<Token>, Vec<Span>)> {
         let start_index = current_index;
 
         // Try to parse a token
-        let mut token_parser = alt((block_comment, line_comment, keyword, int, single_char_token));
+        let mut token_parser = alt((
+            block_comment,
+            line_comment,
+            verbatim,
+            directive,
+            macro_expansion,
+            string,
+            number,
+            keyword,
+            word,
+            single_char_token,
+            unknown,
+        ));
 
         let parser_result = token_parser.parse(current_input);
 
@@ -168,10 +184,13 @@ pub fn scan(input: &str) -> IResult<&str, (Vec<Token>, Vec<Span>)> {
                 let consumed = current_input.len() - remaining.len();
                 let end_index = start_index + consumed;
 
+                // Line comments swallow their newline; keep it out of the span
+                let lexeme = current_input[..consumed].trim_end();
+
                 tokens.push(token);
                 spans.push(Span {
                     start: start_index,
-                    end: end_index,
+                    end: start_index + lexeme.len(),
                 });
                 current_input = remaining;
                 current_index = end_index;
@@ -184,8 +203,7 @@ pub fn scan(input: &str) -> IResult<&str, (Vec<Token>, Vec<Span>)> {
 }
 
 fn line_comment(input: &str) -> IResult<&str, Token> {
-    let (remaining, comment) =
-        delimited(tag("//"), nom::bytes::complete::take_until("//"), tag("//")).parse(input)?;
+    let (remaining, _) = (tag("//"), not_line_ending, opt(line_ending)).parse(input)?;
     Ok((remaining, Token::LineComment))
 }
 
@@ -204,7 +222,6 @@ fn single_char_token(input: &str) -> IResult<&str, Token> {
         tag(";"),
         tag("/"),
         tag("*"),
-        tag("="),
     ))
     .parse(input)?;
 
@@ -228,7 +245,7 @@ fn single_char_token(input: &str) -> IResult<&str, Token> {
     Ok((remaining, token_type))
 }
 fn block_comment(input: &str) -> IResult<&str, Token> {
-    let (remaining, comment) =
+    let (remaining, _) =
         delimited(tag("/*"), nom::bytes::complete::take_until("*/"), tag("*/")).parse(input)?;
     Ok((remaining, Token::BlockComment))
 }
@@ -245,6 +262,123 @@ fn int(input: &str) -> IResult<&str, Token> {
     Ok((remaining, Token::Int(number)))
 }
 
+/// An integer or floating point literal such as `-1`, `0.5` or `1e-05`.
+fn number(input: &str) -> IResult<&str, Token> {
+    let error = || nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Float));
+
+    let (remaining, text) =
+        recognize_float(input).map_err(|_: nom::Err<nom::error::Error<&str>>| error())?;
+
+    // `2D` or `1stOrder` are words rather than a number followed by a word
+    if remaining.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        return Err(error());
+    }
+
+    let (_, token) = if text.contains(['.', 'e', 'E']) {
+        float(text)?
+    } else {
+        alt((int, float)).parse(text)?
+    };
+
+    Ok((remaining, token))
+}
+
+/// Recognise an OpenFOAM word. Words start with a letter or underscore and end at whitespace
+/// or punctuation, except that they may contain balanced parentheses as in `div(phi,U)`.
+fn word_text(input: &str) -> IResult<&str, &str> {
+    let error = || nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Alpha));
+
+    if !input.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        return Err(error());
+    }
+
+    let mut open_parens = Vec::new();
+    let mut end = input.len();
+    for (i, c) in input.char_indices() {
+        match c {
+            '(' => open_parens.push(i),
+            ')' if open_parens.pop().is_some() => {}
+            ')' | ';' | '{' | '}' | '[' | ']' | '"' | '$' => {
+                end = i;
+                break;
+            }
+            '/' if input[i..].starts_with("//") || input[i..].starts_with("/*") => {
+                end = i;
+                break;
+            }
+            c if c.is_whitespace() => {
+                end = i;
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    // An unclosed parenthesis is not part of the word, e.g. `div(phi` while typing
+    if let Some(&first_open) = open_parens.first() {
+        end = end.min(first_open);
+    }
+
+    Ok((&input[end..], &input[..end]))
+}
+
+fn word(input: &str) -> IResult<&str, Token> {
+    let (remaining, _) = word_text(input)?;
+    Ok((remaining, Token::Word))
+}
+
+/// A double-quoted string. An unterminated string runs to the end of its line.
+fn string(input: &str) -> IResult<&str, Token> {
+    let (body, _) = tag("\"").parse(input)?;
+
+    let mut escaped = false;
+    for (i, c) in body.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return Ok((&body[i + 1..], Token::String)),
+            _ => escaped = false,
+        }
+    }
+
+    let (remaining, _) = not_line_ending(body)?;
+    Ok((remaining, Token::String))
+}
+
+/// A `$name`, `$:scoped.name` or `${name}` macro expansion.
+fn macro_expansion(input: &str) -> IResult<&str, Token> {
+    let (remaining, _) = (
+        tag("$"),
+        alt((
+            recognize(delimited(tag("{"), take_until("}"), tag("}"))),
+            take_while1(|c: char| c.is_alphanumeric() || "_.:/!^<>-".contains(c)),
+        )),
+    )
+        .parse(input)?;
+    Ok((remaining, Token::Macro))
+}
+
+/// A `#include`-style directive keyword.
+fn directive(input: &str) -> IResult<&str, Token> {
+    let (remaining, _) = (tag("#"), alpha1).parse(input)?;
+    Ok((remaining, Token::Directive))
+}
+
+/// A `#{ ... #}` verbatim code block, running to the end of the input if unterminated.
+fn verbatim(input: &str) -> IResult<&str, Token> {
+    let (remaining, _) = (
+        tag("#{"),
+        alt((recognize((take_until("#}"), tag("#}"))), rest)),
+    )
+        .parse(input)?;
+    Ok((remaining, Token::Verbatim))
+}
+
+/// Any single character no other token accepts.
+fn unknown(input: &str) -> IResult<&str, Token> {
+    let (remaining, _) = anychar(input)?;
+    Ok((remaining, Token::Unknown))
+}
+
 pub fn get_foam_definition(input: Token) -> String {
     let definition = match input {
         Token::FoamFile => {
@@ -316,9 +450,9 @@ pub fn get_foam_definition(input: Token) -> String {
     definition.to_string()
 }
 
-/// Return a token from the input string which is a Lox keyword
+/// Return a token from the input string which is an OpenFOAM keyword
 fn keyword(input: &str) -> IResult<&str, Token> {
-    let (remaining, lexeme) = alphanumeric1(input)?;
+    let (remaining, lexeme) = word_text(input)?;
     let token_type = match lexeme {
         "FoamFile" => Token::FoamFile,
         "convertToMeters" => Token::ConvertToMeters,
@@ -382,44 +516,53 @@ pub fn get_errors(tokens: &[Token], spans: &[Span]) -> HashMap<Span, String> {
     let mut errors = HashMap::new();
 
     for (i, (token, span)) in tokens.iter().zip(spans.iter()).enumerate() {
-        match token {
-            Token::Uniform => {
-                // Check that the following tokens are: LeftBrace, Int, Int, Int, RightBrace
-                if i + 4 < tokens.len() {
-                    if tokens[i + 1] != Token::LeftBrace {
-                        errors.insert(
-                            *span,
-                            format!("Expected {:?}, found {:?}", Token::LeftBrace, tokens[i + 1]),
-                        );
-                    }
-                    if !matches!(tokens[i + 2], Token::Int(_)) {
-                        errors.insert(*span, format!("Expected Int, found {:?}", tokens[i + 2]));
-                    }
-                    if !matches!(tokens[i + 3], Token::Int(_)) {
-                        errors.insert(*span, format!("Expected Int, found {:?}", tokens[i + 3]));
-                    }
-                    if !matches!(tokens[i + 4], Token::Int(_)) {
-                        errors.insert(*span, format!("Expected Int, found {:?}", tokens[i + 4]));
-                    }
-                    if tokens[i + 5] != Token::RightBrace {
-                        errors.insert(
-                            *span,
-                            format!(
-                                "Expected {:?}, found {:?}",
-                                Token::RightBrace,
-                                tokens[i + 5]
-                            ),
-                        );
-                    }
-                    if tokens[i + 6] != Token::Semicolon {
-                        errors.insert(
-                            *span,
-                            format!("Expected {:?}, found {:?}", Token::Semicolon, tokens[i + 6]),
-                        );
-                    }
+        // `uniform` is followed by a scalar or a parenthesised vector/tensor, then a semicolon
+        if *token != Token::Uniform {
+            continue;
+        }
+
+        let is_number =
+            |token: Option<&Token>| matches!(token, Some(Token::Int(_) | Token::Float(_)));
+
+        let semicolon_index = match tokens.get(i + 1) {
+            Some(Token::LeftParen) => {
+                let mut j = i + 2;
+                while is_number(tokens.get(j)) {
+                    j += 1;
+                }
+                if tokens.get(j) != Some(&Token::RightParen) {
+                    errors.insert(
+                        *span,
+                        format!(
+                            "Expected {:?}, found {:?}",
+                            Token::RightParen,
+                            tokens.get(j)
+                        ),
+                    );
+                    continue;
                 }
+                j + 1
             }
-            _ => {}
+            next if is_number(next) => i + 2,
+            Some(Token::Macro) => continue,
+            next => {
+                errors.insert(
+                    *span,
+                    format!("Expected a value after uniform, found {next:?}"),
+                );
+                continue;
+            }
+        };
+
+        if tokens.get(semicolon_index) != Some(&Token::Semicolon) {
+            errors.insert(
+                *span,
+                format!(
+                    "Expected {:?}, found {:?}",
+                    Token::Semicolon,
+                    tokens.get(semicolon_index)
+                ),
+            );
         }
     }
 
@@ -436,8 +579,8 @@ pub fn get_inline_hints(tokens: &[Token], spans: &[Span]) -> HashMap<Span, Strin
             // If they match this add the following hints for the Int token spans:
             // kg, meters, seconds, kelvin, moles, amps, candela
 
-            if i + 8 < tokens.len() {
-                let unit_labels = vec!["kg", "m", "s", "K", "mol", "A", "cd"];
+            if i + 9 < tokens.len() {
+                let unit_labels = ["kg", "m", "s", "K", "mol", "A", "cd"];
                 let mut all_match = true;
 
                 // Check LeftBracket
@@ -447,7 +590,7 @@ pub fn get_inline_hints(tokens: &[Token], spans: &[Span]) -> HashMap<Span, Strin
 
                 // Check the 7 Int tokens and RightBracket
                 for j in 0..7 {
-                    if !matches!(tokens[i + 2 + j], Token::Int(_)) {
+                    if !matches!(tokens[i + 2 + j], Token::Int(_) | Token::Float(_)) {
                         all_match = false;
                         break;
                     }
@@ -460,9 +603,8 @@ pub fn get_inline_hints(tokens: &[Token], spans: &[Span]) -> HashMap<Span, Strin
 
                 // If all tokens match the expected pattern, add hints for the Int tokens
                 if all_match {
-                    for j in 0..7 {
-                        let token_index = i + 2 + j;
-                        hints.insert(spans[token_index].clone(), unit_labels[j].to_string());
+                    for (j, label) in unit_labels.iter().enumerate() {
+                        hints.insert(spans[i + 2 + j], label.to_string());
                     }
                 }
             }
@@ -471,23 +613,6 @@ pub fn get_inline_hints(tokens: &[Token], spans: &[Span]) -> HashMap<Span, Strin
     hints
 }
 
-pub fn token_color(token: Token) -> String {
-    match token {
-        Token::Hex => "#FF0000".to_string(),
-        Token::VolVectorField => "#00FF00".to_string(),
-        Token::Object => "#0000FF".to_string(),
-        Token::U => "#FFFF00".to_string(),
-        Token::Uniform => "#FF00FF".to_string(),
-        Token::MovingWall => "#00FFFF".to_string(),
-        Token::FixedValue => "#800080".to_string(),
-        Token::FrontAndBack => "#808080".to_string(),
-        Token::NoSlip => "#FFA500".to_string(),
-        Token::Empty => "#800000".to_string(),
-        Token::FixedWalls => "#008000".to_string(),
-        _ => "#FFFFFF".to_string(),
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -495,7 +620,7 @@ mod tests {
     #[test]
     fn test_foam_keywords() {
         let input = "hex (0 1 2 3 4 5 6 7) (40 40 1) simpleGrading (1 1 1)";
-        let (remaining, token) = keyword(input).unwrap();
+        let (_, token) = keyword(input).unwrap();
         assert_eq!(token, Token::Hex);
     }
 
@@ -510,11 +635,80 @@ mod tests {
     fn test_comment() {
         let input = "// This is a comment\n";
         let (remaining, comment) = line_comment(input).unwrap();
+        assert_eq!(remaining, "");
+        assert_eq!(comment, Token::LineComment);
+    }
+
+    #[test]
+    fn test_scan_recovers_from_unknown_words() {
+        let input = "div(phi,U) bounded Gauss linearUpwind grad(U);\ninternalField $U; #include \"initialConditions\"\np_rgh 1e-05 -1 ~";
+        let (remaining, (tokens, spans)) = scan(input).unwrap();
+
         assert_eq!(remaining, "");
         assert_eq!(
-            comment,
-            Token::LineComment(" This is a comment".to_string())
+            tokens,
+            vec![
+                Token::Word,
+                Token::Word,
+                Token::Word,
+                Token::Word,
+                Token::Word,
+                Token::Semicolon,
+                Token::InternalField,
+                Token::Macro,
+                Token::Semicolon,
+                Token::Directive,
+                Token::String,
+                Token::Word,
+                Token::Float(1e-05),
+                Token::Int(-1),
+                Token::Unknown,
+            ]
         );
+        assert_eq!(&input[spans[0].start..spans[0].end], "div(phi,U)");
+        assert_eq!(&input[spans[4].start..spans[4].end], "grad(U)");
+    }
+
+    #[test]
+    fn test_scan_strings_macros_and_code_blocks() {
+        let input = "name \"a \\\"b\\\"\"; $var; ${FOAM_CASE}/mesh code #{ x = 1; #} \"open";
+        let (remaining, (tokens, spans)) = scan(input).unwrap();
+
+        assert_eq!(remaining, "");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word,
+                Token::String,
+                Token::Semicolon,
+                Token::Macro,
+                Token::Semicolon,
+                Token::Macro,
+                Token::Slash,
+                Token::Word,
+                Token::Word,
+                Token::Verbatim,
+                Token::String,
+            ]
+        );
+        let text = |index: usize| &input[spans[index].start..spans[index].end];
+        assert_eq!(text(1), "\"a \\\"b\\\"\"");
+        assert_eq!(text(5), "${FOAM_CASE}");
+        assert_eq!(text(9), "#{ x = 1; #}");
+        assert_eq!(text(10), "\"open");
+    }
+
+    #[test]
+    fn test_uniform_values_are_checked() {
+        let errors = |input: &str| {
+            let (_, (tokens, spans)) = scan(input).unwrap();
+            get_errors(&tokens, &spans).len()
+        };
+        assert_eq!(errors("value uniform 1;"), 0);
+        assert_eq!(errors("value uniform (1 0 -2.5);"), 0);
+        assert_eq!(errors("value uniform $inlet;"), 0);
+        assert_eq!(errors("value uniform (1 0 a);"), 1);
+        assert_eq!(errors("value uniform (1 0 0)"), 1);
     }
 
     #[test]
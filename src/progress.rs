@@ -0,0 +1,210 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use dashmap::DashMap;
+use tower_lsp::Client;
+use tower_lsp::lsp_types::notification::Progress as ProgressNotification;
+use tower_lsp::lsp_types::request::WorkDoneProgressCreate;
+use tower_lsp::lsp_types::{
+    NumberOrString, ProgressParams, ProgressParamsValue, WorkDoneProgress, WorkDoneProgressBegin,
+    WorkDoneProgressCreateParams, WorkDoneProgressEnd, WorkDoneProgressReport,
+};
+
+/// Hands out `window/workDoneProgress` tokens and records which ones the client cancelled.
+#[derive(Debug, Default)]
+pub struct ProgressTracker {
+    supported: AtomicBool,
+    next_id: AtomicU64,
+    cancelled: DashMap<NumberOrString, Arc<AtomicBool>>,
+}
+
+impl ProgressTracker {
+    /// Record whether the client advertised `window.workDoneProgress`.
+    pub fn set_supported(&self, supported: bool) {
+        self.supported.store(supported, Ordering::Relaxed);
+    }
+
+    /// Start a cancellable progress report titled `title`.
+    ///
+    /// If the client does not support progress, or refuses to create it, the returned
+    /// `Progress` silently ignores reports but still tracks cancellation.
+    pub async fn begin(self: &Arc<Self>, client: &Client, title: &str) -> Progress {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let token = NumberOrString::String(format!("ofoam_ls/{id}"));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.cancelled.insert(token.clone(), cancelled.clone());
+
+        let mut progress = Progress {
+            client: client.clone(),
+            token: None,
+            cancelled,
+            tracker: self.clone(),
+            percentage: 0,
+        };
+
+        if !self.supported.load(Ordering::Relaxed) {
+            return progress;
+        }
+
+        let created = client
+            .send_request::<WorkDoneProgressCreate>(WorkDoneProgressCreateParams {
+                token: token.clone(),
+            })
+            .await;
+        if let Err(error) = created {
+            tracing::debug!(%error, "client refused to create progress");
+            return progress;
+        }
+
+        progress.token = Some(token);
+        progress
+            .send(WorkDoneProgress::Begin(WorkDoneProgressBegin {
+                title: title.to_string(),
+                cancellable: Some(true),
+                message: None,
+                percentage: Some(0),
+            }))
+            .await;
+        progress
+    }
+
+    /// Handle `window/workDoneProgress/cancel`.
+    pub fn cancel(&self, token: &NumberOrString) {
+        if let Some(cancelled) = self.cancelled.get(token) {
+            cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// A running progress report, ended by `Progress::end`.
+#[derive(Debug)]
+pub struct Progress {
+    client: Client,
+    /// `None` when the client is not showing this progress.
+    token: Option<NumberOrString>,
+    cancelled: Arc<AtomicBool>,
+    tracker: Arc<ProgressTracker>,
+    percentage: u32,
+}
+
+impl Progress {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Report that `done` out of `total` steps are complete. Only changes of a whole
+    /// percent are sent to the client.
+    pub async fn report(&mut self, done: usize, total: usize, message: impl Into<String>) {
+        let percentage = (done * 100).checked_div(total).unwrap_or(100) as u32;
+        if percentage == self.percentage {
+            return;
+        }
+        self.percentage = percentage;
+
+        self.send(WorkDoneProgress::Report(WorkDoneProgressReport {
+            cancellable: Some(true),
+            message: Some(message.into()),
+            percentage: Some(percentage),
+        }))
+        .await;
+    }
+
+    pub async fn end(self, message: impl Into<String>) {
+        self.send(WorkDoneProgress::End(WorkDoneProgressEnd {
+            message: Some(message.into()),
+        }))
+        .await;
+
+        self.tracker
+            .cancelled
+            .retain(|_, flag| !Arc::ptr_eq(flag, &self.cancelled));
+    }
+
+    async fn send(&self, value: WorkDoneProgress) {
+        let Some(token) = self.token.clone() else {
+            return;
+        };
+        self.client
+            .send_notification::<ProgressNotification>(ProgressParams {
+                token,
+                value: ProgressParamsValue::WorkDone(value),
+            })
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::jsonrpc::Result;
+    use tower_lsp::lsp_types::{InitializeParams, InitializeResult};
+    use tower_lsp::{LanguageServer, LspService};
+
+    struct Server;
+
+    #[tower_lsp::async_trait]
+    impl LanguageServer for Server {
+        async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+            Ok(InitializeResult::default())
+        }
+
+        async fn shutdown(&self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// A client whose connection has not been initialised, so it refuses to create progress.
+    fn client() -> Client {
+        let mut client = None;
+        let _service = LspService::new(|connection| {
+            client = Some(connection);
+            Server
+        });
+        client.unwrap()
+    }
+
+    fn token(id: u64) -> NumberOrString {
+        NumberOrString::String(format!("ofoam_ls/{id}"))
+    }
+
+    #[tokio::test]
+    async fn test_cancel_marks_only_its_progress() {
+        let tracker = Arc::new(ProgressTracker::default());
+        let client = client();
+        let first = tracker.begin(&client, "Indexing").await;
+        let second = tracker.begin(&client, "Indexing").await;
+
+        tracker.cancel(&token(1));
+        tracker.cancel(&token(7));
+        assert!(!first.is_cancelled());
+        assert!(second.is_cancelled());
+
+        first.end("Indexed").await;
+        second.end("Cancelled").await;
+        assert!(tracker.cancelled.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_refused_progress_still_tracks_cancellation() {
+        let tracker = Arc::new(ProgressTracker::default());
+        tracker.set_supported(true);
+        let progress = tracker.begin(&client(), "Indexing").await;
+        assert_eq!(progress.token, None);
+
+        tracker.cancel(&token(0));
+        assert!(progress.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_report_tracks_whole_percentages() {
+        let tracker = Arc::new(ProgressTracker::default());
+        let mut progress = tracker.begin(&client(), "Indexing").await;
+
+        progress.report(1, 3, "0/U").await;
+        assert_eq!(progress.percentage, 33);
+        progress.report(3, 3, "0/p").await;
+        assert_eq!(progress.percentage, 100);
+        progress.report(0, 0, "").await;
+        assert_eq!(progress.percentage, 100);
+    }
+}
@@ -0,0 +1,533 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use regex::Regex;
+use tower_lsp::lsp_types::SymbolKind;
+
+use crate::ast::{Dictionary, Entry, Value, Word};
+use crate::case::Case;
+use crate::document::Document;
+use crate::file_kind::FileKind;
+use crate::parser::Span;
+use crate::symbols;
+
+/// Keys whose values name cell, face or point zones, in `fvModels`, `MRFProperties`, the
+/// sources of `topoSetDict` and `setFieldsDict`, and elsewhere.
+const ZONE_KEYS: &[&str] = &[
+    "cellZone",
+    "cellZones",
+    "faceZone",
+    "faceZones",
+    "pointZone",
+    "zone",
+    "zones",
+];
+
+/// What the cases name and refer to across their files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entity {
+    Patch,
+    Field,
+    Zone,
+}
+
+/// A place where a file names a patch, a field or a zone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mention<'a> {
+    pub entity: Entity,
+    /// The name, or a regular expression for a quoted key such as `"(inlet|outlet)"`
+    pub name: &'a str,
+    pub span: Span,
+    /// Whether `name` is a regular expression matching names rather than a name
+    pub pattern: bool,
+    /// Whether this is where the entity is defined: a mesh patch, a field file or a zone
+    pub declaration: bool,
+}
+
+impl<'a> Mention<'a> {
+    pub fn matches(&self, entity: Entity, name: &str, patterns: &mut Patterns<'a>) -> bool {
+        if self.entity != entity {
+            return false;
+        }
+        if !self.pattern {
+            return self.name == name;
+        }
+        patterns.is_match(self.name, name)
+    }
+}
+
+/// Quoted keys compiled to regular expressions, `None` for those that do not compile. Kept
+/// for one search, which matches the same few patterns against every name in a case.
+#[derive(Debug, Default)]
+pub struct Patterns<'a>(HashMap<&'a str, Option<Regex>>);
+
+impl<'a> Patterns<'a> {
+    fn is_match(&mut self, pattern: &'a str, name: &str) -> bool {
+        self.0
+            .entry(pattern)
+            .or_insert_with(|| Regex::new(&format!("^(?:{pattern})$")).ok())
+            .as_ref()
+            .is_some_and(|regex| regex.is_match(name))
+    }
+}
+
+/// The patch, field or zone named at `offset`.
+pub fn entity_at<'a>(
+    document: &'a Document,
+    path: &Path,
+    offset: usize,
+    case: &Case,
+) -> Option<Mention<'a>> {
+    mentions(document, path, &case.fields())
+        .into_iter()
+        .find(|mention| {
+            !mention.pattern && mention.span.start <= offset && offset <= mention.span.end
+        })
+}
+
+/// Every mention of the entity across `files`, patterns matching it included. Where a
+/// quoted key names it outright, as `U` in `"(U|k)Final"`, only that name is given.
+pub fn references<'a>(
+    files: &[(&'a Path, &'a Document)],
+    case: &Case,
+    entity: Entity,
+    name: &str,
+) -> Vec<(&'a Path, Mention<'a>)> {
+    let fields = case.fields();
+    let mut references = Vec::new();
+    let mut patterns = Patterns::default();
+    for (path, document) in files {
+        let matching: Vec<_> = mentions(document, path, &fields)
+            .into_iter()
+            .filter(|mention| mention.matches(entity, name, &mut patterns))
+            .collect();
+        let named_inside = |pattern: &Mention| {
+            matching.iter().any(|mention| {
+                !mention.pattern
+                    && pattern.span.start <= mention.span.start
+                    && mention.span.end <= pattern.span.end
+            })
+        };
+        references.extend(
+            matching
+                .iter()
+                .filter(|mention| !mention.pattern || !named_inside(mention))
+                .map(|mention| (*path, mention.clone())),
+        );
+    }
+    references.sort_by(|(a, x), (b, y)| a.cmp(b).then(x.span.start.cmp(&y.span.start)));
+    references
+}
+
+/// The patches, fields and zones a file names. Names in keys such as `div(phi,U)` count as
+/// fields only when they are among the case's `fields`, which keeps fluxes such as `phi` out.
+pub fn mentions<'a>(document: &'a Document, path: &Path, fields: &[&str]) -> Vec<Mention<'a>> {
+    let ast = &document.ast;
+    let mut mentions = Vec::new();
+
+    for definition in symbols::definitions(document) {
+        let entity = match definition.kind {
+            SymbolKind::INTERFACE => Entity::Patch,
+            SymbolKind::STRUCT => Entity::Zone,
+            _ => continue,
+        };
+        mentions.push(Mention {
+            entity,
+            name: definition.name,
+            span: definition.span,
+            pattern: false,
+            declaration: true,
+        });
+    }
+    for word in keyed_words(ast, ZONE_KEYS) {
+        words(&mut mentions, Entity::Zone, word);
+    }
+
+    match document.kind {
+        FileKind::Field(_) => {
+            if let Some(object) = ast
+                .dict("FoamFile")
+                .and_then(|header| header.get("object")?.word())
+            {
+                mentions.push(Mention {
+                    entity: Entity::Field,
+                    name: &object.text,
+                    span: object.span,
+                    pattern: false,
+                    declaration: true,
+                });
+            }
+            for entry in ast
+                .dict("boundaryField")
+                .map_or(&[][..], |dict| &dict.entries)
+            {
+                if let Entry::Dict { key, .. } = entry {
+                    words(&mut mentions, Entity::Patch, key);
+                }
+            }
+        }
+        FileKind::FvSchemes => {
+            for entry in &ast.entries {
+                if let Entry::Dict { key, dict, .. } = entry
+                    && (key.text.ends_with("Schemes") || key.text == "fluxRequired")
+                {
+                    field_keys(&mut mentions, dict);
+                }
+            }
+        }
+        FileKind::FvSolution => {
+            for entry in &ast.entries {
+                let Entry::Dict { key, dict, .. } = entry else {
+                    continue;
+                };
+                match key.text.as_str() {
+                    "solvers" => field_keys(&mut mentions, dict),
+                    "relaxationFactors" => {
+                        field_keys(&mut mentions, dict);
+                        for group in ["fields", "equations"] {
+                            if let Some(dict) = dict.dict(group) {
+                                field_keys(&mut mentions, dict);
+                            }
+                        }
+                    }
+                    _ => {
+                        for control in ["residualControl", "outerCorrectorResidualControl"] {
+                            if let Some(dict) = dict.dict(control) {
+                                field_keys(&mut mentions, dict);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        FileKind::ControlDict => {
+            if let Some(functions) = ast.dict("functions") {
+                function_objects(&mut mentions, functions);
+            }
+        }
+        FileKind::SetFieldsDict => set_fields(&mut mentions, &ast.entries),
+        FileKind::SnappyHexMeshDict => {
+            let layers = ast
+                .dict("addLayersControls")
+                .and_then(|controls| controls.dict("layers"));
+            for entry in layers.map_or(&[][..], |dict| &dict.entries) {
+                if let Some(key) = entry.key() {
+                    words(&mut mentions, Entity::Patch, key);
+                }
+            }
+        }
+        _ if path
+            .file_name()
+            .is_some_and(|name| name == "createPatchDict") =>
+        {
+            if let Some(Entry::Value { values, .. }) = ast.get("patches") {
+                create_patches(&mut mentions, values);
+            }
+        }
+        _ => {}
+    }
+
+    // Zones defined by a keyed value, such as the `cellZone` of a refinement surface, are
+    // found twice
+    mentions.sort_by_key(|mention| (mention.span.start, !mention.declaration));
+    mentions.dedup_by_key(|mention| (mention.span, mention.name));
+    mentions.retain(|mention| {
+        mention.entity != Entity::Field
+            || mention.pattern
+            || mention.declaration
+            || fields.contains(&mention.name)
+    });
+    mentions
+}
+
+/// A word naming an entity: a name, or for a quoted regular expression the pattern and the
+/// names spelled out in it.
+fn words<'a>(mentions: &mut Vec<Mention<'a>>, entity: Entity, word: &'a Word) {
+    if !is_pattern(word) {
+        mentions.push(Mention {
+            entity,
+            name: &word.text,
+            span: word.span,
+            pattern: false,
+            declaration: false,
+        });
+        return;
+    }
+    names(mentions, entity, word);
+    mentions.push(Mention {
+        entity,
+        name: &word.text,
+        span: word.span,
+        pattern: true,
+        declaration: false,
+    });
+}
+
+/// The names within a key such as `div(phi,U)`, `"(U|k|epsilon)Final"` or `alpha.water`,
+/// with `Final` taken off. Words followed by `(` are functions and operators, as `T` in
+/// `dev2(T(grad(U)))`, not names.
+fn names<'a>(mentions: &mut Vec<Mention<'a>>, entity: Entity, word: &'a Word) {
+    let is_name = |c: char| c.is_alphanumeric() || c == '_' || c == '.';
+    let start = word.span.start + usize::from(word.quoted);
+    let mut rest = word.text.as_str();
+    let mut offset = 0;
+    while let Some(first) = rest.find(is_name) {
+        let length = rest[first..]
+            .find(|c| !is_name(c))
+            .unwrap_or(rest.len() - first);
+        let name = &rest[first..first + length];
+        let call = rest[first + length..].starts_with('(');
+        let name = match name.strip_suffix("Final") {
+            Some(field) if !field.is_empty() => field,
+            _ => name,
+        };
+        if !call && name.chars().any(char::is_alphabetic) {
+            mentions.push(Mention {
+                entity,
+                name,
+                span: Span {
+                    start: start + offset + first,
+                    end: start + offset + first + name.len(),
+                },
+                pattern: false,
+                declaration: false,
+            });
+        }
+        offset += first + length;
+        rest = &rest[first + length..];
+    }
+}
+
+fn field_keys<'a>(mentions: &mut Vec<Mention<'a>>, dict: &'a Dictionary) {
+    for entry in &dict.entries {
+        let Some(key) = entry.key() else {
+            continue;
+        };
+        if key.text == "default" {
+            continue;
+        }
+        if is_pattern(key) {
+            words(mentions, Entity::Field, key);
+        } else {
+            names(mentions, Entity::Field, key);
+        }
+    }
+}
+
+/// The `patches`, `patch`, `fields` and `field` entries of function objects, and the `name`
+/// of a patch a `surfaceFieldValue` samples.
+fn function_objects<'a>(mentions: &mut Vec<Mention<'a>>, functions: &'a Dictionary) {
+    for word in keyed_words(functions, &["patches", "patch"]) {
+        words(mentions, Entity::Patch, word);
+    }
+    for word in keyed_words(functions, &["fields", "field"]) {
+        words(mentions, Entity::Field, word);
+    }
+    for entry in &functions.entries {
+        if let Entry::Dict { dict, .. } = entry
+            && dict
+                .get("regionType")
+                .and_then(Entry::word)
+                .is_some_and(|region| region.text == "patch")
+            && let Some(name) = dict.get("name").and_then(Entry::word)
+        {
+            words(mentions, Entity::Patch, name);
+        }
+    }
+}
+
+/// The fields after `volScalarFieldValue` and its kin in `defaultFieldValues` and the
+/// `fieldValues` of each region.
+fn set_fields<'a>(mentions: &mut Vec<Mention<'a>>, entries: &'a [Entry]) {
+    fn in_values<'a>(mentions: &mut Vec<Mention<'a>>, values: &'a [Value]) {
+        for pair in values.windows(2) {
+            if let [Value::Word(setter), Value::Word(field)] = pair
+                && setter.text.ends_with("FieldValue")
+            {
+                words(mentions, Entity::Field, field);
+            }
+        }
+        for value in values {
+            match value {
+                Value::List { items, .. } => in_values(mentions, items),
+                Value::Dict(dict) => set_fields(mentions, &dict.entries),
+                _ => {}
+            }
+        }
+    }
+    for entry in entries {
+        match entry {
+            Entry::Value { values: list, .. } | Entry::Bare { values: list, .. } => {
+                in_values(mentions, list)
+            }
+            Entry::Dict { dict, .. } => set_fields(mentions, &dict.entries),
+            _ => {}
+        }
+    }
+}
+
+/// The patches `createPatchDict` makes, and those it makes them from.
+fn create_patches<'a>(mentions: &mut Vec<Mention<'a>>, values: &'a [Value]) {
+    let patches = values.iter().flat_map(|value| match value {
+        Value::List { items, .. } => items.as_slice(),
+        _ => &[],
+    });
+    for patch in patches {
+        let Value::Dict(dict) = patch else {
+            continue;
+        };
+        if let Some(name) = dict.get("name").and_then(Entry::word) {
+            mentions.push(Mention {
+                entity: Entity::Patch,
+                name: &name.text,
+                span: name.span,
+                pattern: false,
+                declaration: true,
+            });
+        }
+        for word in keyed_words(dict, &["patches"]) {
+            words(mentions, Entity::Patch, word);
+        }
+    }
+}
+
+/// The words and strings given to any of `keys`, at any depth.
+fn keyed_words<'a>(dict: &'a Dictionary, keys: &[&str]) -> Vec<&'a Word> {
+    fn in_values<'a>(values: &'a [Value], keys: &[&str], all: bool, found: &mut Vec<&'a Word>) {
+        for value in values {
+            match value {
+                Value::Word(word) | Value::String(word) if all => found.push(word),
+                Value::List { items, .. } => in_values(items, keys, all, found),
+                Value::Dict(dict) => in_dictionary(dict, keys, found),
+                _ => {}
+            }
+        }
+    }
+    fn in_dictionary<'a>(dict: &'a Dictionary, keys: &[&str], found: &mut Vec<&'a Word>) {
+        for entry in &dict.entries {
+            match entry {
+                Entry::Dict { dict, .. } => in_dictionary(dict, keys, found),
+                Entry::Value { key, values, .. } => {
+                    in_values(values, keys, keys.contains(&key.text.as_str()), found)
+                }
+                Entry::Bare { values, .. } => in_values(values, keys, false, found),
+                _ => {}
+            }
+        }
+    }
+    let mut found = Vec::new();
+    in_dictionary(dict, keys, &mut found);
+    found
+}
+
+/// Quoted keys and list items are regular expressions when they use any of its syntax.
+fn is_pattern(word: &Word) -> bool {
+    word.quoted
+        && word
+            .text
+            .contains(['.', '*', '+', '?', '(', ')', '[', ']', '|', '^', '$'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_kind::FieldClass;
+
+    fn case() -> Case {
+        Case::with_files(
+            "/case",
+            &[
+                (
+                    "constant/polyMesh/boundary",
+                    "1\n(\n    movingWall\n    {\n        type wall;\n    }\n)\n",
+                    FileKind::Boundary,
+                ),
+                (
+                    "0/U",
+                    "FoamFile { class volVectorField; object U; }\nboundaryField\n{\n    movingWall { type noSlip; }\n    \"(moving|fixed)Walls?\" { type noSlip; }\n    \"(inlet|movingWall)\" { type noSlip; }\n}\n",
+                    FileKind::Field(Some(FieldClass::Vector)),
+                ),
+                (
+                    "system/fvSchemes",
+                    "divSchemes\n{\n    default none;\n    div(phi,U) Gauss linear;\n}\n",
+                    FileKind::FvSchemes,
+                ),
+                (
+                    "system/fvSolution",
+                    "solvers\n{\n    \"(U|k)Final\" { solver PBiCGStab; }\n    \".*\" { solver smoothSolver; }\n}\n",
+                    FileKind::FvSolution,
+                ),
+                (
+                    "system/controlDict",
+                    "functions\n{\n    forces { type forces; patches (movingWall); fields (p U); }\n}\n",
+                    FileKind::ControlDict,
+                ),
+                (
+                    "system/topoSetDict",
+                    "actions\n(\n    { name rotor; type cellZoneSet; action new; }\n);\n",
+                    FileKind::TopoSetDict,
+                ),
+                (
+                    "constant/MRFProperties",
+                    "MRF1 { cellZone rotor; }\n",
+                    FileKind::Dictionary,
+                ),
+            ],
+        )
+    }
+
+    fn found(entity: Entity, name: &str) -> Vec<(String, String)> {
+        let case = case();
+        let files: Vec<_> = case
+            .files
+            .iter()
+            .map(|(path, document)| (path.as_path(), document))
+            .collect();
+        references(&files, &case, entity, name)
+            .into_iter()
+            .map(|(path, mention)| {
+                let document = files.iter().find(|(file, _)| *file == path).unwrap().1;
+                let text = &document.text[mention.span.start..mention.span.end];
+                (path.to_string_lossy().into_owned(), text.to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_patch_references_include_matching_patterns() {
+        assert_eq!(
+            found(Entity::Patch, "movingWall"),
+            [
+                ("/case/0/U", "movingWall"),
+                ("/case/0/U", "\"(moving|fixed)Walls?\""),
+                ("/case/0/U", "movingWall"),
+                ("/case/constant/polyMesh/boundary", "movingWall"),
+                ("/case/system/controlDict", "movingWall"),
+            ]
+            .map(|(path, text)| (path.to_string(), text.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_field_and_zone_references() {
+        assert_eq!(
+            found(Entity::Field, "U"),
+            [
+                ("/case/0/U", "U"),
+                ("/case/system/controlDict", "U"),
+                ("/case/system/fvSchemes", "U"),
+                ("/case/system/fvSolution", "U"),
+                ("/case/system/fvSolution", "\".*\""),
+            ]
+            .map(|(path, text)| (path.to_string(), text.to_string()))
+        );
+        assert_eq!(
+            found(Entity::Zone, "rotor"),
+            [
+                ("/case/constant/MRFProperties", "rotor"),
+                ("/case/system/topoSetDict", "rotor"),
+            ]
+            .map(|(path, text)| (path.to_string(), text.to_string()))
+        );
+    }
+}
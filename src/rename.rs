@@ -0,0 +1,369 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use tower_lsp::lsp_types::{
+    DocumentChangeOperation, DocumentChanges, OneOf, OptionalVersionedTextDocumentIdentifier,
+    Range, RenameFile, ResourceOp, TextDocumentEdit, TextEdit, Url, WorkspaceEdit,
+};
+
+use crate::case::Case;
+use crate::document::Document;
+use crate::parser::Span;
+use crate::references::{self, Entity, Mention, Patterns};
+
+/// The edits renaming an entity, and the regular expressions that matched it but will not
+/// match the new name.
+#[derive(Debug)]
+pub struct Rename {
+    pub edit: WorkspaceEdit,
+    pub warnings: Vec<String>,
+}
+
+/// The name at `offset` if it can be renamed: a patch or field named outright rather than
+/// matched by a pattern.
+pub fn prepare<'a>(
+    document: &'a Document,
+    path: &Path,
+    offset: usize,
+    case: &Case,
+) -> Option<Mention<'a>> {
+    references::entity_at(document, path, offset, case)
+        .filter(|mention| matches!(mention.entity, Entity::Patch | Entity::Field))
+}
+
+/// Rename the patch or field at `offset` in `path` to `new_name` throughout `files`. Renaming
+/// a field renames its files in the time directories too, through file operations when the
+/// client supports them.
+pub fn rename(
+    files: &[(&Path, &Document)],
+    path: &Path,
+    offset: usize,
+    case: &Case,
+    new_name: &str,
+    file_operations: bool,
+) -> Result<Rename, String> {
+    let (_, document) = files
+        .iter()
+        .find(|(file, _)| *file == path)
+        .ok_or("The file is not part of the case")?;
+    let mention =
+        prepare(document, path, offset, case).ok_or("Only patch and field names can be renamed")?;
+    if !is_valid_name(new_name) {
+        return Err(format!("`{new_name}` is not a valid name"));
+    }
+    let renames = match mention.entity {
+        Entity::Field => field_renames(case, mention.name, new_name)?,
+        _ => Vec::new(),
+    };
+
+    let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+    let mut warnings = Vec::new();
+    let mut patterns = Patterns::default();
+    for (file, reference) in references::references(files, case, mention.entity, mention.name) {
+        let Some((_, document)) = files.iter().find(|(path, _)| *path == file) else {
+            continue;
+        };
+        if reference.pattern {
+            if !reference.matches(mention.entity, new_name, &mut patterns) {
+                let range = document.range_of(reference.span);
+                warnings.push(format!(
+                    "`{}` in {}:{} matches `{}` but not `{new_name}`",
+                    document.text[reference.span.start..reference.span.end].trim_matches('"'),
+                    relative(case, file),
+                    range.start.line + 1,
+                    mention.name,
+                ));
+            }
+            continue;
+        }
+        let Ok(uri) = Url::from_file_path(file) else {
+            continue;
+        };
+        changes.entry(uri).or_default().push(TextEdit {
+            range: name_range(document, reference.span),
+            new_text: new_name.to_string(),
+        });
+    }
+
+    if renames.is_empty() || !file_operations {
+        if !renames.is_empty() {
+            let files: Vec<_> = renames
+                .iter()
+                .map(|(from, _)| relative(case, from))
+                .collect();
+            warnings.push(format!(
+                "Rename {} to `{new_name}` by hand; the editor cannot rename files",
+                files.join(", ")
+            ));
+        }
+        return Ok(Rename {
+            edit: WorkspaceEdit {
+                changes: Some(changes),
+                ..WorkspaceEdit::default()
+            },
+            warnings,
+        });
+    }
+
+    // Edit the files under their old names, then rename them
+    let mut changes: Vec<_> = changes.into_iter().collect();
+    changes.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+    let mut operations: Vec<_> = changes
+        .into_iter()
+        .map(|(uri, edits)| {
+            DocumentChangeOperation::Edit(TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier { uri, version: None },
+                edits: edits.into_iter().map(OneOf::Left).collect(),
+            })
+        })
+        .collect();
+    for (from, to) in renames {
+        let (Ok(old_uri), Ok(new_uri)) = (Url::from_file_path(from), Url::from_file_path(&to))
+        else {
+            continue;
+        };
+        operations.push(DocumentChangeOperation::Op(ResourceOp::Rename(
+            RenameFile {
+                old_uri,
+                new_uri,
+                options: None,
+                annotation_id: None,
+            },
+        )));
+    }
+    Ok(Rename {
+        edit: WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(operations)),
+            ..WorkspaceEdit::default()
+        },
+        warnings,
+    })
+}
+
+/// The field files to rename, such as `0/T` to `0/T.water` and `0.orig/T.orig` to
+/// `0.orig/T.water.orig`.
+fn field_renames<'a>(
+    case: &'a Case,
+    name: &str,
+    new_name: &str,
+) -> Result<Vec<(&'a Path, PathBuf)>, String> {
+    let mut renames = Vec::new();
+    for file in case.field_files(name) {
+        let orig = file
+            .file_name()
+            .is_some_and(|file_name| file_name.to_string_lossy().ends_with(".orig"));
+        let to = file.with_file_name(if orig {
+            format!("{new_name}.orig")
+        } else {
+            new_name.to_string()
+        });
+        if case.files.contains_key(&to) || to.exists() {
+            return Err(format!("{} already exists", relative(case, &to)));
+        }
+        renames.push((file, to));
+    }
+    Ok(renames)
+}
+
+fn relative(case: &Case, path: &Path) -> String {
+    path.strip_prefix(&case.root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// The range of a name, inside the quotes if it has them.
+fn name_range(document: &Document, span: Span) -> Range {
+    let text = &document.text[span.start..span.end];
+    if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+        return document.range_of(Span {
+            start: span.start + 1,
+            end: span.end - 1,
+        });
+    }
+    document.range_of(span)
+}
+
+/// OpenFOAM words cannot hold spaces, quotes or the punctuation that ends them.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.contains(|c: char| {
+            c.is_whitespace()
+                || matches!(
+                    c,
+                    '"' | '\'' | ';' | '{' | '}' | '(' | ')' | '[' | ']' | '$' | '#' | '/' | '\\'
+                )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_kind::{FieldClass, FileKind};
+
+    fn files(case: &Case) -> Vec<(&Path, &Document)> {
+        let mut files: Vec<_> = case
+            .files
+            .iter()
+            .map(|(path, document)| (path.as_path(), document))
+            .collect();
+        files.sort_by_key(|(path, _)| *path);
+        files
+    }
+
+    fn apply(document: &Document, edits: &[TextEdit]) -> String {
+        let mut text = document.text.clone();
+        let mut edits = edits.to_vec();
+        edits.sort_by_key(|edit| std::cmp::Reverse(document.offset_at(edit.range.start)));
+        for edit in edits {
+            let start = document.offset_at(edit.range.start);
+            let end = document.offset_at(edit.range.end);
+            text.replace_range(start..end, &edit.new_text);
+        }
+        text
+    }
+
+    #[test]
+    fn test_rename_patch_across_the_case() {
+        let case = Case::with_files(
+            "/case",
+            &[
+                (
+                    "system/blockMeshDict",
+                    "boundary\n(\n    movingWall\n    {\n        type wall;\n    }\n);\n",
+                    FileKind::BlockMeshDict,
+                ),
+                (
+                    "0/U",
+                    "boundaryField\n{\n    movingWall { type noSlip; }\n    \"(inlet|movingWall)\" { type noSlip; }\n    \"moving.*\" { type noSlip; }\n}\n",
+                    FileKind::Field(Some(FieldClass::Vector)),
+                ),
+            ],
+        );
+        let files = files(&case);
+
+        let field = Path::new("/case/0/U");
+        let offset = case.files[field].text.find("movingWall").unwrap();
+        let renamed = rename(&files, field, offset, &case, "lid", true).unwrap();
+
+        let changes = renamed.edit.changes.unwrap();
+        let edited = |file: &str| {
+            let path = case.root.join(file);
+            apply(
+                &case.files[&path],
+                &changes[&Url::from_file_path(path).unwrap()],
+            )
+        };
+        assert_eq!(
+            edited("system/blockMeshDict"),
+            "boundary\n(\n    lid\n    {\n        type wall;\n    }\n);\n"
+        );
+        assert_eq!(
+            edited("0/U"),
+            "boundaryField\n{\n    lid { type noSlip; }\n    \"(inlet|lid)\" { type noSlip; }\n    \"moving.*\" { type noSlip; }\n}\n"
+        );
+        assert_eq!(
+            renamed.warnings,
+            ["`moving.*` in 0/U:5 matches `movingWall` but not `lid`"]
+        );
+
+        assert!(rename(&files, field, offset, &case, "a b", true).is_err());
+        assert!(rename(&files, field, 0, &case, "lid", true).is_err());
+    }
+
+    #[test]
+    fn test_rename_field_and_its_files() {
+        let scalar = FileKind::Field(Some(FieldClass::Scalar));
+        let case = Case::with_files(
+            "/case",
+            &[
+                (
+                    "0/T",
+                    "FoamFile { class volScalarField; object T; }\n",
+                    scalar,
+                ),
+                (
+                    "0.orig/T.orig",
+                    "FoamFile { class volScalarField; object T; }\n",
+                    scalar,
+                ),
+                (
+                    "system/fvSchemes",
+                    "divSchemes\n{\n    div(phi,T) Gauss linear;\n    div((nuEff*dev2(T(grad(U))))) Gauss linear;\n}\n",
+                    FileKind::FvSchemes,
+                ),
+                (
+                    "system/fvSolution",
+                    "solvers\n{\n    \"(T|k)Final\" {}\n    \"[TU]\" {}\n}\n",
+                    FileKind::FvSolution,
+                ),
+                (
+                    "system/setFieldsDict",
+                    "defaultFieldValues (volScalarFieldValue T 300);\n",
+                    FileKind::SetFieldsDict,
+                ),
+            ],
+        );
+        let files = files(&case);
+
+        let schemes = Path::new("/case/system/fvSchemes");
+        let offset = case.files[schemes].text.find(",T").unwrap() + 1;
+        let renamed = rename(&files, schemes, offset, &case, "T.water", true).unwrap();
+        assert_eq!(
+            renamed.warnings,
+            ["`[TU]` in system/fvSolution:4 matches `T` but not `T.water`"]
+        );
+
+        let Some(DocumentChanges::Operations(operations)) = renamed.edit.document_changes else {
+            panic!("no document changes");
+        };
+        let mut edited = Vec::new();
+        let mut moved = Vec::new();
+        for operation in operations {
+            match operation {
+                DocumentChangeOperation::Edit(edit) => {
+                    let (_, document) = files
+                        .iter()
+                        .find(|(path, _)| {
+                            Url::from_file_path(path).unwrap() == edit.text_document.uri
+                        })
+                        .unwrap();
+                    let edits: Vec<_> = edit
+                        .edits
+                        .into_iter()
+                        .filter_map(|edit| match edit {
+                            OneOf::Left(edit) => Some(edit),
+                            OneOf::Right(_) => None,
+                        })
+                        .collect();
+                    edited.push(apply(document, &edits));
+                }
+                DocumentChangeOperation::Op(ResourceOp::Rename(rename)) => {
+                    moved.push((
+                        rename.old_uri.path().to_string(),
+                        rename.new_uri.path().to_string(),
+                    ));
+                }
+                DocumentChangeOperation::Op(_) => panic!("unexpected operation"),
+            }
+        }
+        assert_eq!(
+            edited,
+            [
+                "FoamFile { class volScalarField; object T.water; }\n",
+                "FoamFile { class volScalarField; object T.water; }\n",
+                "divSchemes\n{\n    div(phi,T.water) Gauss linear;\n    div((nuEff*dev2(T(grad(U))))) Gauss linear;\n}\n",
+                "solvers\n{\n    \"(T.water|k)Final\" {}\n    \"[TU]\" {}\n}\n",
+                "defaultFieldValues (volScalarFieldValue T.water 300);\n",
+            ]
+        );
+        assert_eq!(
+            moved,
+            [
+                ("/case/0/T", "/case/0/T.water"),
+                ("/case/0.orig/T.orig", "/case/0.orig/T.water.orig"),
+            ]
+            .map(|(from, to)| (from.to_string(), to.to_string()))
+        );
+    }
+}
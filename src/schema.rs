@@ -0,0 +1,1583 @@
+use crate::file_kind::{FieldClass, FileKind};
+use KeyValue::{Choice, Dict, List, Value};
+
+/// Words with a fixed meaning wherever they appear as values.
+pub const KEYWORDS: &[&str] = &[
+    "uniform",
+    "nonuniform",
+    "on",
+    "off",
+    "true",
+    "false",
+    "yes",
+    "no",
+    "none",
+    "hex",
+    "simpleGrading",
+    "edgeGrading",
+    "arc",
+    "spline",
+    "polyLine",
+    "BSpline",
+    "latestTime",
+    "firstTime",
+    "startTime",
+    "endTime",
+    "writeNow",
+    "noWriteNow",
+    "nextWrite",
+    "timeStep",
+    "runTime",
+    "adjustableRunTime",
+    "cpuTime",
+    "clockTime",
+    "ascii",
+    "binary",
+    "general",
+    "fixed",
+    "scientific",
+    "laminar",
+    "RAS",
+    "LES",
+];
+
+/// Which fields or patches a boundary condition can be used for.
+#[derive(Debug, Clone, Copy)]
+pub enum Applies {
+    /// Any field
+    Any,
+    /// Fields of this class
+    Class(FieldClass),
+    /// Fields with one of these names
+    Fields(&'static [&'static str]),
+    /// Patches in `polyMesh/boundary` and `blockMeshDict` only
+    Mesh,
+    /// Both fields and the mesh, as for `empty` and the other constraint types
+    Constraint,
+}
+
+/// What completion inserts for a boundary condition parameter.
+#[derive(Debug, Clone, Copy)]
+pub enum Placeholder {
+    /// A field value of the patch field's class, e.g. `uniform (0 0 0)`
+    Field,
+    /// A scalar field value whatever the class of the patch field
+    ScalarField,
+    /// A `Function1` of the patch field's class, e.g. `constant 0`
+    Function1,
+    /// Literal text
+    Text(&'static str),
+}
+
+#[derive(Debug)]
+pub struct BoundaryCondition {
+    pub name: &'static str,
+    pub doc: &'static str,
+    pub applies: Applies,
+    /// Parameters without a default, in the order they are usually written
+    pub parameters: &'static [(&'static str, Placeholder)],
+}
+
+const fn bc(
+    name: &'static str,
+    doc: &'static str,
+    applies: Applies,
+    parameters: &'static [(&'static str, Placeholder)],
+) -> BoundaryCondition {
+    BoundaryCondition {
+        name,
+        doc,
+        applies,
+        parameters,
+    }
+}
+
+const VALUE: &[(&str, Placeholder)] = &[("value", Placeholder::Field)];
+const NUT: &[&str] = &["nut"];
+
+/// Boundary conditions of the standard distribution, with the geometric patch types used in
+/// `polyMesh/boundary` and `blockMeshDict`.
+pub const BOUNDARY_CONDITIONS: &[BoundaryCondition] = &[
+    // Geometric and constraint types
+    bc("patch", "Generic patch.", Applies::Mesh, &[]),
+    bc(
+        "wall",
+        "Wall, where wall functions apply.",
+        Applies::Mesh,
+        &[],
+    ),
+    bc(
+        "mappedPatch",
+        "Patch mapped to another region.",
+        Applies::Mesh,
+        &[],
+    ),
+    bc(
+        "mappedWall",
+        "Wall mapped to another region.",
+        Applies::Mesh,
+        &[],
+    ),
+    bc(
+        "empty",
+        "Direction not solved for in 2D and 1D cases.",
+        Applies::Constraint,
+        &[],
+    ),
+    bc(
+        "symmetry",
+        "Symmetry boundary of any shape.",
+        Applies::Constraint,
+        &[],
+    ),
+    bc(
+        "symmetryPlane",
+        "Planar symmetry boundary.",
+        Applies::Constraint,
+        &[],
+    ),
+    bc(
+        "wedge",
+        "Side of an axisymmetric wedge.",
+        Applies::Constraint,
+        &[],
+    ),
+    bc(
+        "cyclic",
+        "Periodic boundary coupled to its neighbour patch.",
+        Applies::Constraint,
+        &[],
+    ),
+    bc(
+        "cyclicAMI",
+        "Periodic boundary with non-conformal faces.",
+        Applies::Constraint,
+        &[],
+    ),
+    bc(
+        "processor",
+        "Boundary between processors of a decomposed case.",
+        Applies::Constraint,
+        &[],
+    ),
+    // Basic
+    bc(
+        "calculated",
+        "Value set by the solver from other fields.",
+        Applies::Any,
+        VALUE,
+    ),
+    bc(
+        "fixedValue",
+        "Fixed value (Dirichlet).",
+        Applies::Any,
+        VALUE,
+    ),
+    bc(
+        "zeroGradient",
+        "Zero normal gradient (Neumann).",
+        Applies::Any,
+        &[],
+    ),
+    bc(
+        "fixedGradient",
+        "Fixed normal gradient.",
+        Applies::Any,
+        &[("gradient", Placeholder::Field)],
+    ),
+    bc(
+        "mixed",
+        "Blend of fixed value and fixed gradient set by valueFraction.",
+        Applies::Any,
+        &[
+            ("refValue", Placeholder::Field),
+            ("refGradient", Placeholder::Field),
+            ("valueFraction", Placeholder::ScalarField),
+        ],
+    ),
+    bc(
+        "noSlip",
+        "Zero velocity at a wall.",
+        Applies::Class(FieldClass::Vector),
+        &[],
+    ),
+    bc(
+        "slip",
+        "Zero normal component, zero gradient tangential components.",
+        Applies::Any,
+        &[],
+    ),
+    bc(
+        "movingWallVelocity",
+        "Velocity of a moving wall, corrected for mesh motion.",
+        Applies::Class(FieldClass::Vector),
+        VALUE,
+    ),
+    bc(
+        "uniformFixedValue",
+        "Fixed value varying in time.",
+        Applies::Any,
+        &[("uniformValue", Placeholder::Function1)],
+    ),
+    bc(
+        "uniformFixedGradient",
+        "Fixed gradient varying in time.",
+        Applies::Any,
+        &[("uniformGradient", Placeholder::Function1)],
+    ),
+    bc(
+        "codedFixedValue",
+        "Fixed value computed by user code.",
+        Applies::Any,
+        &[
+            ("name", Placeholder::Text("myCondition")),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "codedMixed",
+        "Mixed condition computed by user code.",
+        Applies::Any,
+        &[
+            ("name", Placeholder::Text("myCondition")),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    // Inlet and outlet
+    bc(
+        "inletOutlet",
+        "Zero gradient for outflow, fixed inletValue for backflow.",
+        Applies::Any,
+        &[
+            ("inletValue", Placeholder::Field),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "outletInlet",
+        "Zero gradient for inflow, fixed outletValue for outflow.",
+        Applies::Any,
+        &[
+            ("outletValue", Placeholder::Field),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "uniformInletOutlet",
+        "inletOutlet with an inlet value varying in time.",
+        Applies::Any,
+        &[
+            ("uniformInletValue", Placeholder::Function1),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "freestream",
+        "inletOutlet towards a free-stream value.",
+        Applies::Any,
+        &[("freestreamValue", Placeholder::Field)],
+    ),
+    bc(
+        "freestreamVelocity",
+        "Free-stream velocity, switching between inflow and outflow.",
+        Applies::Class(FieldClass::Vector),
+        &[("freestreamValue", Placeholder::Field)],
+    ),
+    bc(
+        "freestreamPressure",
+        "Free-stream pressure, switching between inflow and outflow.",
+        Applies::Class(FieldClass::Scalar),
+        &[("freestreamValue", Placeholder::Field)],
+    ),
+    bc(
+        "flowRateInletVelocity",
+        "Inlet velocity normal to the patch from a volumetric flow rate.",
+        Applies::Class(FieldClass::Vector),
+        &[
+            ("volumetricFlowRate", Placeholder::Text("constant 1")),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "flowRateOutletVelocity",
+        "Outlet velocity normal to the patch from a volumetric flow rate.",
+        Applies::Class(FieldClass::Vector),
+        &[
+            ("volumetricFlowRate", Placeholder::Text("constant 1")),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "surfaceNormalFixedValue",
+        "Velocity normal to the patch; negative values point into the domain.",
+        Applies::Class(FieldClass::Vector),
+        &[("refValue", Placeholder::ScalarField)],
+    ),
+    bc(
+        "pressureInletOutletVelocity",
+        "Velocity for pressure boundaries: zero gradient out, from the flux in.",
+        Applies::Class(FieldClass::Vector),
+        VALUE,
+    ),
+    bc(
+        "pressureInletVelocity",
+        "Inlet velocity from the flux through a pressure inlet.",
+        Applies::Class(FieldClass::Vector),
+        VALUE,
+    ),
+    bc(
+        "totalPressure",
+        "Static pressure from a total pressure p0 and the local velocity.",
+        Applies::Class(FieldClass::Scalar),
+        &[("p0", Placeholder::Field), ("value", Placeholder::Field)],
+    ),
+    bc(
+        "totalTemperature",
+        "Static temperature from a total temperature T0 and the local velocity.",
+        Applies::Fields(&["T"]),
+        &[("T0", Placeholder::Field), ("value", Placeholder::Field)],
+    ),
+    bc(
+        "fixedFluxPressure",
+        "Pressure gradient matching the boundary flux.",
+        Applies::Class(FieldClass::Scalar),
+        VALUE,
+    ),
+    bc(
+        "fixedMean",
+        "Value whose patch average is fixed.",
+        Applies::Any,
+        &[
+            ("meanValue", Placeholder::Text("0")),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "prghPressure",
+        "p_rgh from a fixed static pressure.",
+        Applies::Fields(&["p_rgh"]),
+        &[("p", Placeholder::Field), ("value", Placeholder::Field)],
+    ),
+    bc(
+        "prghTotalPressure",
+        "p_rgh from a fixed total pressure.",
+        Applies::Fields(&["p_rgh"]),
+        &[("p0", Placeholder::Field), ("value", Placeholder::Field)],
+    ),
+    bc(
+        "turbulentInlet",
+        "Random fluctuations about a reference value.",
+        Applies::Any,
+        &[
+            ("referenceField", Placeholder::Field),
+            ("fluctuationScale", Placeholder::Text("0.02")),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "turbulentIntensityKineticEnergyInlet",
+        "Turbulent kinetic energy from an intensity and the local velocity.",
+        Applies::Fields(&["k"]),
+        &[
+            ("intensity", Placeholder::Text("0.05")),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "turbulentMixingLengthDissipationRateInlet",
+        "Dissipation rate from a mixing length and k.",
+        Applies::Fields(&["epsilon"]),
+        &[
+            ("mixingLength", Placeholder::Text("0.01")),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "turbulentMixingLengthFrequencyInlet",
+        "Specific dissipation rate from a mixing length and k.",
+        Applies::Fields(&["omega"]),
+        &[
+            ("mixingLength", Placeholder::Text("0.01")),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "waveTransmissive",
+        "Outlet letting waves leave without reflection.",
+        Applies::Any,
+        &[
+            ("gamma", Placeholder::Text("1.4")),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "advective",
+        "Outlet advecting the field out of the domain.",
+        Applies::Any,
+        &[],
+    ),
+    bc(
+        "variableHeightFlowRate",
+        "Phase fraction bounded at an inlet with a flow rate.",
+        Applies::Class(FieldClass::Scalar),
+        &[
+            ("lowerBound", Placeholder::Text("0")),
+            ("upperBound", Placeholder::Text("1")),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "variableHeightFlowRateInletVelocity",
+        "Inlet velocity from a flow rate through the wetted part of the patch.",
+        Applies::Class(FieldClass::Vector),
+        &[
+            ("flowRate", Placeholder::Text("constant 1")),
+            ("alpha", Placeholder::Text("alpha.water")),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "timeVaryingMappedFixedValue",
+        "Fixed value interpolated from data in constant/boundaryData.",
+        Applies::Any,
+        &[],
+    ),
+    bc(
+        "fixedProfile",
+        "Fixed value following a profile along a direction.",
+        Applies::Any,
+        &[
+            ("profile", Placeholder::Text("csvFile")),
+            ("direction", Placeholder::Text("(0 1 0)")),
+            ("origin", Placeholder::Text("0")),
+        ],
+    ),
+    // Wall functions
+    bc(
+        "kqRWallFunction",
+        "Zero gradient wall condition for k, q and R.",
+        Applies::Fields(&["k", "q", "R"]),
+        VALUE,
+    ),
+    bc(
+        "kLowReWallFunction",
+        "Wall condition for k valid at low and high Reynolds numbers.",
+        Applies::Fields(&["k"]),
+        VALUE,
+    ),
+    bc(
+        "epsilonWallFunction",
+        "Wall function for the dissipation rate.",
+        Applies::Fields(&["epsilon"]),
+        VALUE,
+    ),
+    bc(
+        "omegaWallFunction",
+        "Wall function for the specific dissipation rate.",
+        Applies::Fields(&["omega"]),
+        VALUE,
+    ),
+    bc(
+        "nutkWallFunction",
+        "Turbulent viscosity from k at a wall.",
+        Applies::Fields(NUT),
+        VALUE,
+    ),
+    bc(
+        "nutUWallFunction",
+        "Turbulent viscosity from the velocity at a wall.",
+        Applies::Fields(NUT),
+        VALUE,
+    ),
+    bc(
+        "nutLowReWallFunction",
+        "Zero turbulent viscosity for resolved boundary layers.",
+        Applies::Fields(NUT),
+        VALUE,
+    ),
+    bc(
+        "nutUSpaldingWallFunction",
+        "Turbulent viscosity from Spalding's law, valid across the boundary layer.",
+        Applies::Fields(NUT),
+        VALUE,
+    ),
+    bc(
+        "nutkRoughWallFunction",
+        "Turbulent viscosity from k at a rough wall.",
+        Applies::Fields(NUT),
+        &[
+            ("Ks", Placeholder::ScalarField),
+            ("Cs", Placeholder::ScalarField),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "alphatWallFunction",
+        "Turbulent thermal diffusivity at a wall.",
+        Applies::Fields(&["alphat"]),
+        &[
+            ("Prt", Placeholder::Text("0.85")),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "compressible::alphatWallFunction",
+        "Turbulent thermal diffusivity at a wall, for compressible solvers.",
+        Applies::Fields(&["alphat"]),
+        &[
+            ("Prt", Placeholder::Text("0.85")),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "compressible::alphatJayatillekeWallFunction",
+        "Turbulent thermal diffusivity from Jayatilleke's thermal wall function.",
+        Applies::Fields(&["alphat"]),
+        &[
+            ("Prt", Placeholder::Text("0.85")),
+            ("value", Placeholder::Field),
+        ],
+    ),
+    bc(
+        "v2WallFunction",
+        "Wall function for v2.",
+        Applies::Fields(&["v2"]),
+        VALUE,
+    ),
+    bc(
+        "fWallFunction",
+        "Wall function for f.",
+        Applies::Fields(&["f"]),
+        VALUE,
+    ),
+    // Thermal
+    bc(
+        "externalWallHeatFluxTemperature",
+        "Wall temperature from a heat flux or an external heat transfer coefficient.",
+        Applies::Fields(&["T"]),
+        &[
+            ("q", Placeholder::ScalarField),
+            ("kappaMethod", Placeholder::Text("fluidThermo")),
+            ("value", Placeholder::Field),
+        ],
+    ),
+];
+
+impl BoundaryCondition {
+    /// Whether the condition fits a field of `class` named `field`, either of which may be
+    /// unknown. `mesh` is set for patches of the mesh rather than of a field.
+    pub fn applies_to(&self, class: Option<FieldClass>, field: Option<&str>, mesh: bool) -> bool {
+        match self.applies {
+            Applies::Mesh => mesh,
+            Applies::Constraint => true,
+            _ if mesh => false,
+            Applies::Any => true,
+            Applies::Class(wanted) => class.is_none_or(|class| class == wanted),
+            Applies::Fields(names) => field.is_none_or(|field| names.contains(&field)),
+        }
+    }
+}
+
+/// Keys and values that still work but have been renamed.
+pub const DEPRECATED: &[&str] = &["RASModel", "LESModel", "compressed", "uncompressed"];
+
+pub fn is_keyword(word: &str) -> bool {
+    KEYWORDS.contains(&word) || word.starts_with("List<")
+}
+
+pub fn boundary_condition(name: &str) -> Option<&'static BoundaryCondition> {
+    BOUNDARY_CONDITIONS
+        .iter()
+        .find(|condition| condition.name == name)
+}
+
+pub fn is_deprecated(word: &str) -> bool {
+    DEPRECATED.contains(&word)
+}
+
+/// A key that may appear in a dictionary, with what completion inserts for its value.
+#[derive(Debug)]
+pub struct Key {
+    pub name: &'static str,
+    pub doc: &'static str,
+    pub value: KeyValue,
+}
+
+#[derive(Debug)]
+pub enum KeyValue {
+    /// A value, defaulting to this one, e.g. `1e-06`
+    Value(&'static str),
+    /// One of these words, the first being the default
+    Choice(&'static [&'static str]),
+    /// A sub-dictionary
+    Dict,
+    /// A list `( ... )`
+    List,
+}
+
+const fn key(name: &'static str, doc: &'static str, value: KeyValue) -> Key {
+    Key { name, doc, value }
+}
+
+const ON_OFF: &[&str] = &["on", "off"];
+const YES_NO: &[&str] = &["yes", "no"];
+const TRUE_FALSE: &[&str] = &["true", "false"];
+
+const FOAM_FILE: &[Key] = &[
+    key("version", "Version of the file format.", Value("2.0")),
+    key(
+        "format",
+        "Whether the data is written as text or binary.",
+        Choice(&["ascii", "binary"]),
+    ),
+    key(
+        "class",
+        "Type of the object held by the file.",
+        Choice(&[
+            "dictionary",
+            "volScalarField",
+            "volVectorField",
+            "volSymmTensorField",
+            "volTensorField",
+            "surfaceScalarField",
+            "pointVectorField",
+            "polyBoundaryMesh",
+        ]),
+    ),
+    key(
+        "location",
+        "Directory of the file, relative to the case.",
+        Value("\"system\""),
+    ),
+    key(
+        "object",
+        "Name of the object, normally the file name.",
+        Value("controlDict"),
+    ),
+];
+
+const CONTROL_DICT: &[Key] = &[
+    key(
+        "application",
+        "Solver or application run for the case.",
+        Value("foamRun"),
+    ),
+    key(
+        "solver",
+        "Solver module run by foamRun.",
+        Value("incompressibleFluid"),
+    ),
+    key(
+        "startFrom",
+        "Where the run starts: the startTime entry, or the first or latest time directory.",
+        Choice(&["startTime", "firstTime", "latestTime"]),
+    ),
+    key(
+        "startTime",
+        "Start time when startFrom is startTime.",
+        Value("0"),
+    ),
+    key(
+        "stopAt",
+        "When the run stops.",
+        Choice(&["endTime", "writeNow", "noWriteNow", "nextWrite"]),
+    ),
+    key("endTime", "End time when stopAt is endTime.", Value("1000")),
+    key("deltaT", "Time step.", Value("1")),
+    key(
+        "writeControl",
+        "How writeInterval is measured.",
+        Choice(&[
+            "timeStep",
+            "runTime",
+            "adjustableRunTime",
+            "cpuTime",
+            "clockTime",
+        ]),
+    ),
+    key(
+        "writeInterval",
+        "Interval between writes, in units set by writeControl.",
+        Value("100"),
+    ),
+    key(
+        "purgeWrite",
+        "Number of time directories kept; 0 keeps all.",
+        Value("0"),
+    ),
+    key(
+        "writeFormat",
+        "Format of written fields.",
+        Choice(&["ascii", "binary"]),
+    ),
+    key(
+        "writePrecision",
+        "Significant figures of written data.",
+        Value("6"),
+    ),
+    key(
+        "writeCompression",
+        "Whether written files are gzip compressed.",
+        Choice(ON_OFF),
+    ),
+    key(
+        "timeFormat",
+        "Format of time directory names.",
+        Choice(&["general", "fixed", "scientific"]),
+    ),
+    key(
+        "timePrecision",
+        "Significant figures of time directory names.",
+        Value("6"),
+    ),
+    key(
+        "runTimeModifiable",
+        "Whether dictionaries are re-read when they change.",
+        Choice(TRUE_FALSE),
+    ),
+    key(
+        "adjustTimeStep",
+        "Whether deltaT adapts to maxCo.",
+        Choice(&["no", "yes"]),
+    ),
+    key(
+        "maxCo",
+        "Largest Courant number allowed with adjustTimeStep.",
+        Value("1"),
+    ),
+    key(
+        "maxDeltaT",
+        "Largest time step allowed with adjustTimeStep.",
+        Value("1"),
+    ),
+    key("libs", "Extra libraries loaded at start-up.", List),
+    key(
+        "functions",
+        "Function objects run alongside the solver.",
+        Dict,
+    ),
+];
+
+const FUNCTION_OBJECT: &[Key] = &[
+    key("type", "Type of the function object.", Value("forces")),
+    key("libs", "Libraries providing the function object.", List),
+    key(
+        "writeControl",
+        "When results are written.",
+        Choice(&[
+            "timeStep",
+            "writeTime",
+            "runTime",
+            "adjustableRunTime",
+            "onEnd",
+        ]),
+    ),
+    key("writeInterval", "Interval between writes.", Value("1")),
+    key(
+        "executeControl",
+        "When the function object runs.",
+        Choice(&["timeStep", "writeTime", "runTime", "adjustableRunTime"]),
+    ),
+    key("executeInterval", "Interval between runs.", Value("1")),
+    key(
+        "enabled",
+        "Whether the function object runs at all.",
+        Choice(TRUE_FALSE),
+    ),
+    key(
+        "log",
+        "Whether results are also written to the log.",
+        Choice(TRUE_FALSE),
+    ),
+    key(
+        "timeStart",
+        "Time from which the function object runs.",
+        Value("0"),
+    ),
+    key(
+        "timeEnd",
+        "Time after which the function object stops.",
+        Value("1000"),
+    ),
+    key("region", "Mesh region operated on.", Value("region0")),
+    key("field", "Field operated on.", Value("U")),
+    key("fields", "Fields operated on.", List),
+    key("patches", "Patches operated on.", List),
+];
+
+const FV_SCHEMES: &[Key] = &[
+    key("ddtSchemes", "Time derivative schemes, ddt(...).", Dict),
+    key("gradSchemes", "Gradient schemes, grad(...).", Dict),
+    key("divSchemes", "Convection schemes, div(...).", Dict),
+    key(
+        "laplacianSchemes",
+        "Laplacian schemes, laplacian(...).",
+        Dict,
+    ),
+    key(
+        "interpolationSchemes",
+        "Cell to face interpolation schemes.",
+        Dict,
+    ),
+    key("snGradSchemes", "Surface normal gradient schemes.", Dict),
+    key(
+        "wallDist",
+        "Method calculating the distance to the nearest wall.",
+        Dict,
+    ),
+    key(
+        "fluxRequired",
+        "Fields whose flux is needed, e.g. for pressure correction.",
+        Dict,
+    ),
+];
+
+const SCHEME_FAMILY: &[Key] = &[key(
+    "default",
+    "Scheme used for every term without an entry of its own.",
+    Value("none"),
+)];
+
+const WALL_DIST: &[Key] = &[
+    key(
+        "method",
+        "How the distance is calculated.",
+        Choice(&["meshWave", "Poisson", "advectionDiffusion"]),
+    ),
+    key(
+        "nRequired",
+        "Whether the wall normal is calculated too.",
+        Choice(TRUE_FALSE),
+    ),
+];
+
+const FLUX_REQUIRED: &[Key] = &[key(
+    "default",
+    "Whether every field needs its flux.",
+    Choice(YES_NO),
+)];
+
+const FV_SOLUTION: &[Key] = &[
+    key("solvers", "Linear solver settings for each field.", Dict),
+    key("SIMPLE", "Settings of the steady SIMPLE algorithm.", Dict),
+    key(
+        "PIMPLE",
+        "Settings of the transient PIMPLE algorithm.",
+        Dict,
+    ),
+    key("PISO", "Settings of the transient PISO algorithm.", Dict),
+    key(
+        "relaxationFactors",
+        "Under-relaxation of fields and equations.",
+        Dict,
+    ),
+];
+
+const LINEAR_SOLVER: &[Key] = &[
+    key(
+        "solver",
+        "Linear solver.",
+        Choice(&["GAMG", "PCG", "PBiCGStab", "smoothSolver", "diagonal"]),
+    ),
+    key(
+        "tolerance",
+        "Absolute residual at which the solver stops.",
+        Value("1e-06"),
+    ),
+    key(
+        "relTol",
+        "Residual relative to the initial one at which the solver stops.",
+        Value("0.1"),
+    ),
+    key("maxIter", "Largest number of iterations.", Value("1000")),
+    key("minIter", "Smallest number of iterations.", Value("0")),
+];
+
+const GAMG_SOLVER: &[Key] = &[
+    key(
+        "smoother",
+        "Smoother applied on each level.",
+        Choice(&[
+            "GaussSeidel",
+            "symGaussSeidel",
+            "DIC",
+            "DILU",
+            "DICGaussSeidel",
+            "nonBlockingGaussSeidel",
+        ]),
+    ),
+    key(
+        "nCellsInCoarsestLevel",
+        "Cells in the coarsest level, which is solved directly.",
+        Value("10"),
+    ),
+    key(
+        "agglomerator",
+        "How cells are agglomerated into coarser levels.",
+        Choice(&["faceAreaPair", "algebraicPair"]),
+    ),
+    key("mergeLevels", "Levels coarsened at once.", Value("1")),
+    key(
+        "cacheAgglomeration",
+        "Whether the agglomeration is kept between solutions.",
+        Choice(TRUE_FALSE),
+    ),
+    key(
+        "nPreSweeps",
+        "Sweeps before moving to a coarser level.",
+        Value("0"),
+    ),
+    key(
+        "nPostSweeps",
+        "Sweeps after returning from a coarser level.",
+        Value("2"),
+    ),
+    key("nFinestSweeps", "Sweeps on the finest level.", Value("2")),
+    key(
+        "directSolveCoarsest",
+        "Whether the coarsest level is solved directly.",
+        Choice(&["false", "true"]),
+    ),
+];
+
+const PCG_SOLVER: &[Key] = &[key(
+    "preconditioner",
+    "Preconditioner of the symmetric matrix.",
+    Choice(&["DIC", "FDIC", "GAMG", "diagonal", "none"]),
+)];
+
+const PBICGSTAB_SOLVER: &[Key] = &[key(
+    "preconditioner",
+    "Preconditioner of the asymmetric matrix; DIC and FDIC only suit symmetric ones.",
+    Choice(&["DILU", "DIC", "FDIC", "GAMG", "diagonal", "none"]),
+)];
+
+const SMOOTH_SOLVER: &[Key] = &[
+    key(
+        "smoother",
+        "Smoother iterated until the tolerance is met.",
+        Choice(&[
+            "symGaussSeidel",
+            "GaussSeidel",
+            "DIC",
+            "DILU",
+            "DICGaussSeidel",
+        ]),
+    ),
+    key(
+        "nSweeps",
+        "Sweeps between checks of the residual.",
+        Value("1"),
+    ),
+];
+
+const SIMPLE: &[Key] = &[
+    key(
+        "nNonOrthogonalCorrectors",
+        "Extra pressure solutions for non-orthogonal meshes.",
+        Value("0"),
+    ),
+    key(
+        "consistent",
+        "Whether to use the SIMPLEC variant.",
+        Choice(YES_NO),
+    ),
+    key(
+        "momentumPredictor",
+        "Whether the momentum equation is solved before the pressure.",
+        Choice(YES_NO),
+    ),
+    key(
+        "pRefCell",
+        "Cell in which the pressure level is fixed for closed domains.",
+        Value("0"),
+    ),
+    key("pRefValue", "Pressure in pRefCell.", Value("0")),
+    key(
+        "residualControl",
+        "Initial residuals at which the run is considered converged.",
+        Dict,
+    ),
+];
+
+const OUTER_CORRECTOR_RESIDUAL: &[Key] = &[
+    key(
+        "tolerance",
+        "Initial residual below which the outer iterations stop.",
+        Value("1e-4"),
+    ),
+    key(
+        "relTol",
+        "Initial residual relative to that of the first outer iteration.",
+        Value("0"),
+    ),
+];
+
+const PIMPLE: &[Key] = &[
+    key(
+        "nOuterCorrectors",
+        "Outer iterations per time step; 1 gives PISO.",
+        Value("1"),
+    ),
+    key(
+        "nCorrectors",
+        "Pressure corrections per outer iteration.",
+        Value("2"),
+    ),
+    key(
+        "nNonOrthogonalCorrectors",
+        "Extra pressure solutions for non-orthogonal meshes.",
+        Value("0"),
+    ),
+    key(
+        "momentumPredictor",
+        "Whether the momentum equation is solved before the pressure.",
+        Choice(YES_NO),
+    ),
+    key(
+        "pRefCell",
+        "Cell in which the pressure level is fixed for closed domains.",
+        Value("0"),
+    ),
+    key("pRefValue", "Pressure in pRefCell.", Value("0")),
+    key(
+        "turbOnFinalIterOnly",
+        "Whether turbulence is only solved in the last outer iteration.",
+        Choice(TRUE_FALSE),
+    ),
+    key(
+        "outerCorrectorResidualControl",
+        "Residuals at which the outer iterations stop early.",
+        Dict,
+    ),
+    key(
+        "residualControl",
+        "Initial residuals at which the run is considered converged.",
+        Dict,
+    ),
+];
+
+const PISO: &[Key] = &[
+    key(
+        "nCorrectors",
+        "Pressure corrections per time step.",
+        Value("2"),
+    ),
+    key(
+        "nNonOrthogonalCorrectors",
+        "Extra pressure solutions for non-orthogonal meshes.",
+        Value("0"),
+    ),
+    key(
+        "momentumPredictor",
+        "Whether the momentum equation is solved before the pressure.",
+        Choice(YES_NO),
+    ),
+    key(
+        "pRefCell",
+        "Cell in which the pressure level is fixed for closed domains.",
+        Value("0"),
+    ),
+    key("pRefValue", "Pressure in pRefCell.", Value("0")),
+];
+
+const RELAXATION_FACTORS: &[Key] = &[
+    key(
+        "fields",
+        "Relaxation of fields after they are solved, e.g. p.",
+        Dict,
+    ),
+    key(
+        "equations",
+        "Relaxation of equations before they are solved, e.g. U.",
+        Dict,
+    ),
+];
+
+/// Templates for keys named after the fields of the case.
+const SOLVER_FIELD: Key = key("", "Linear solver settings of the field.", Dict);
+const RESIDUAL_CONTROL_FIELD: Key = key(
+    "",
+    "Initial residual of the field below which the run is converged.",
+    Value("1e-3"),
+);
+const OUTER_CORRECTOR_FIELD: Key = key(
+    "",
+    "Residuals of the field at which the outer iterations stop.",
+    Dict,
+);
+const FIELD_RELAXATION: Key = key("", "Relaxation factor of the field.", Value("0.3"));
+const EQUATION_RELAXATION: Key = key("", "Relaxation factor of the equation.", Value("0.7"));
+
+const FIELD: &[Key] = &[
+    key(
+        "dimensions",
+        "Units as exponents of kg, m, s, K, mol, A and cd.",
+        Value("[0 0 0 0 0 0 0]"),
+    ),
+    key(
+        "internalField",
+        "Initial value in the cells.",
+        Value("uniform 0"),
+    ),
+    key("boundaryField", "Boundary condition of each patch.", Dict),
+];
+
+const PATCH_FIELD: &[Key] = &[
+    key("type", "Boundary condition.", Value("zeroGradient")),
+    key("value", "Value on the patch.", Value("uniform 0")),
+];
+
+const BLOCK_MESH_DICT: &[Key] = &[
+    key(
+        "convertToMeters",
+        "Factor scaling the vertex coordinates to metres.",
+        Value("1"),
+    ),
+    key(
+        "scale",
+        "Factor scaling the vertex coordinates to metres.",
+        Value("1"),
+    ),
+    key("vertices", "Corner points of the blocks.", List),
+    key(
+        "blocks",
+        "Hexahedral blocks with their cell counts and grading.",
+        List,
+    ),
+    key("edges", "Curved block edges.", List),
+    key(
+        "boundary",
+        "Patches and the block faces they consist of.",
+        List,
+    ),
+    key(
+        "defaultPatch",
+        "Patch receiving the faces not in any other patch.",
+        Dict,
+    ),
+    key(
+        "mergePatchPairs",
+        "Pairs of patches merged into one mesh.",
+        List,
+    ),
+];
+
+const BLOCK_MESH_PATCH: &[Key] = &[
+    key(
+        "type",
+        "Patch type.",
+        Choice(&[
+            "patch",
+            "wall",
+            "empty",
+            "symmetryPlane",
+            "symmetry",
+            "wedge",
+            "cyclic",
+        ]),
+    ),
+    key(
+        "faces",
+        "Block faces making up the patch, as lists of four vertices.",
+        List,
+    ),
+    key(
+        "neighbourPatch",
+        "Patch coupled to a cyclic patch.",
+        Value(""),
+    ),
+];
+
+const DECOMPOSE_PAR_DICT: &[Key] = &[
+    key(
+        "numberOfSubdomains",
+        "Number of processors the case is split over.",
+        Value("4"),
+    ),
+    key(
+        "method",
+        "Decomposition method.",
+        Choice(&["scotch", "simple", "hierarchical", "manual"]),
+    ),
+    key("simpleCoeffs", "Settings of the simple method.", Dict),
+    key(
+        "hierarchicalCoeffs",
+        "Settings of the hierarchical method.",
+        Dict,
+    ),
+    key(
+        "distributed",
+        "Whether the case data is spread over several disks.",
+        Choice(&["no", "yes"]),
+    ),
+    key("roots", "Case roots on each node when distributed.", List),
+];
+
+const DECOMPOSITION_COEFFS: &[Key] = &[
+    key(
+        "n",
+        "Number of subdomains in the x, y and z directions.",
+        Value("(2 2 1)"),
+    ),
+    key(
+        "order",
+        "Order in which the directions are split.",
+        Value("xyz"),
+    ),
+];
+
+const PHYSICAL_PROPERTIES: &[Key] = &[
+    key(
+        "viscosityModel",
+        "Viscosity model.",
+        Choice(&["constant", "BirdCarreau", "CrossPowerLaw", "powerLaw"]),
+    ),
+    key(
+        "transportModel",
+        "Viscosity model of older versions.",
+        Choice(&["Newtonian", "BirdCarreau", "CrossPowerLaw", "powerLaw"]),
+    ),
+    key(
+        "nu",
+        "Kinematic viscosity.",
+        Value("[0 2 -1 0 0 0 0] 1e-05"),
+    ),
+    key("rho", "Density.", Value("[1 -3 0 0 0 0 0] 1")),
+];
+
+const MOMENTUM_TRANSPORT: &[Key] = &[
+    key(
+        "simulationType",
+        "Turbulence modelling approach.",
+        Choice(&["RAS", "LES", "laminar"]),
+    ),
+    key("RAS", "Settings of the RAS model.", Dict),
+    key("LES", "Settings of the LES model.", Dict),
+    key("laminar", "Settings of the laminar model.", Dict),
+];
+
+const RAS: &[Key] = &[
+    key(
+        "model",
+        "RAS turbulence model.",
+        Choice(&[
+            "kEpsilon",
+            "kOmegaSST",
+            "realizableKE",
+            "RNGkEpsilon",
+            "kOmega",
+            "SpalartAllmaras",
+        ]),
+    ),
+    key(
+        "turbulence",
+        "Whether turbulence is solved.",
+        Choice(ON_OFF),
+    ),
+    key(
+        "printCoeffs",
+        "Whether the model coefficients are printed.",
+        Choice(ON_OFF),
+    ),
+];
+
+const LES: &[Key] = &[
+    key(
+        "model",
+        "LES turbulence model.",
+        Choice(&["Smagorinsky", "WALE", "kEqn", "dynamicKEqn"]),
+    ),
+    key(
+        "turbulence",
+        "Whether turbulence is solved.",
+        Choice(ON_OFF),
+    ),
+    key(
+        "printCoeffs",
+        "Whether the model coefficients are printed.",
+        Choice(ON_OFF),
+    ),
+    key(
+        "delta",
+        "Filter width model.",
+        Choice(&["cubeRootVol", "vanDriest", "smooth"]),
+    ),
+];
+
+const GRAVITY: &[Key] = &[
+    key(
+        "dimensions",
+        "Units of acceleration.",
+        Value("[0 1 -2 0 0 0 0]"),
+    ),
+    key("value", "Gravitational acceleration.", Value("(0 0 -9.81)")),
+];
+
+const SNAPPY_HEX_MESH_DICT: &[Key] = &[
+    key(
+        "castellatedMesh",
+        "Whether to refine and remove cells outside the geometry.",
+        Choice(TRUE_FALSE),
+    ),
+    key(
+        "snap",
+        "Whether to snap the mesh to the surfaces.",
+        Choice(TRUE_FALSE),
+    ),
+    key(
+        "addLayers",
+        "Whether to add boundary layer cells.",
+        Choice(&["false", "true"]),
+    ),
+    key(
+        "geometry",
+        "Surfaces and shapes used by the other steps.",
+        Dict,
+    ),
+    key(
+        "castellatedMeshControls",
+        "Settings of the refinement step.",
+        Dict,
+    ),
+    key("snapControls", "Settings of the snapping step.", Dict),
+    key(
+        "addLayersControls",
+        "Settings of the layer addition step.",
+        Dict,
+    ),
+    key(
+        "meshQualityControls",
+        "Quality criteria the mesh must meet.",
+        Dict,
+    ),
+    key(
+        "mergeTolerance",
+        "Merge distance relative to the bounding box.",
+        Value("1e-6"),
+    ),
+];
+
+const CASTELLATED_MESH_CONTROLS: &[Key] = &[
+    key(
+        "maxLocalCells",
+        "Cells per processor above which refinement is balanced.",
+        Value("100000"),
+    ),
+    key(
+        "maxGlobalCells",
+        "Total cells at which refinement stops.",
+        Value("2000000"),
+    ),
+    key(
+        "minRefinementCells",
+        "Cells marked for refinement below which refinement stops.",
+        Value("10"),
+    ),
+    key(
+        "nCellsBetweenLevels",
+        "Buffer layers between refinement levels.",
+        Value("3"),
+    ),
+    key(
+        "features",
+        "Feature edge files and their refinement levels.",
+        List,
+    ),
+    key(
+        "refinementSurfaces",
+        "Refinement levels of each surface.",
+        Dict,
+    ),
+    key(
+        "refinementRegions",
+        "Refinement levels inside or near regions.",
+        Dict,
+    ),
+    key(
+        "resolveFeatureAngle",
+        "Angle above which intersections are refined to the maximum level.",
+        Value("30"),
+    ),
+    key(
+        "locationInMesh",
+        "Point inside the region that is kept.",
+        Value("(0 0 0)"),
+    ),
+    key(
+        "allowFreeStandingZoneFaces",
+        "Whether zone faces may be free-standing.",
+        Choice(TRUE_FALSE),
+    ),
+];
+
+const SNAP_CONTROLS: &[Key] = &[
+    key(
+        "nSmoothPatch",
+        "Patch smoothing iterations before snapping.",
+        Value("3"),
+    ),
+    key(
+        "tolerance",
+        "Snapping distance relative to the local cell size.",
+        Value("2.0"),
+    ),
+    key(
+        "nSolveIter",
+        "Mesh displacement relaxation iterations.",
+        Value("30"),
+    ),
+    key("nRelaxIter", "Snapping relaxation iterations.", Value("5")),
+    key(
+        "nFeatureSnapIter",
+        "Feature edge snapping iterations.",
+        Value("10"),
+    ),
+    key(
+        "implicitFeatureSnap",
+        "Whether features are detected from the surface.",
+        Choice(&["false", "true"]),
+    ),
+    key(
+        "explicitFeatureSnap",
+        "Whether the features list is used.",
+        Choice(TRUE_FALSE),
+    ),
+];
+
+const ADD_LAYERS_CONTROLS: &[Key] = &[
+    key(
+        "relativeSizes",
+        "Whether thicknesses are relative to the cell size.",
+        Choice(TRUE_FALSE),
+    ),
+    key("layers", "Number of layers on each patch.", Dict),
+    key(
+        "expansionRatio",
+        "Thickness ratio of neighbouring layers.",
+        Value("1.0"),
+    ),
+    key(
+        "finalLayerThickness",
+        "Thickness of the layer furthest from the wall.",
+        Value("0.3"),
+    ),
+    key(
+        "minThickness",
+        "Thickness below which layers are not added.",
+        Value("0.1"),
+    ),
+    key(
+        "nGrow",
+        "Layers of connected faces not extruded.",
+        Value("0"),
+    ),
+    key(
+        "featureAngle",
+        "Angle above which layers are not extruded.",
+        Value("60"),
+    ),
+    key("nRelaxIter", "Snapping relaxation iterations.", Value("3")),
+    key(
+        "nSmoothSurfaceNormals",
+        "Surface normal smoothing iterations.",
+        Value("1"),
+    ),
+    key(
+        "nSmoothNormals",
+        "Interior normal smoothing iterations.",
+        Value("3"),
+    ),
+    key(
+        "nSmoothThickness",
+        "Thickness smoothing iterations.",
+        Value("10"),
+    ),
+    key(
+        "maxFaceThicknessRatio",
+        "Face aspect ratio above which layers stop growing.",
+        Value("0.5"),
+    ),
+    key(
+        "maxThicknessToMedialRatio",
+        "Thickness to medial distance ratio above which layers shrink.",
+        Value("0.3"),
+    ),
+    key(
+        "minMedialAxisAngle",
+        "Angle used to pick medial axis points.",
+        Value("90"),
+    ),
+    key(
+        "nBufferCellsNoExtrude",
+        "Cells between extruded and not extruded faces.",
+        Value("0"),
+    ),
+    key(
+        "nLayerIter",
+        "Largest number of layer addition iterations.",
+        Value("50"),
+    ),
+];
+
+/// The keys that may appear in the dictionary at `path` of a file of this kind.
+pub fn keys(kind: FileKind, path: &[&str]) -> &'static [Key] {
+    use FileKind as K;
+    match (kind, path) {
+        (_, ["FoamFile"]) => FOAM_FILE,
+        (K::ControlDict, []) => CONTROL_DICT,
+        (K::ControlDict, ["functions", _]) => FUNCTION_OBJECT,
+        (K::FvSchemes, []) => FV_SCHEMES,
+        (K::FvSchemes, ["wallDist"]) => WALL_DIST,
+        (K::FvSchemes, ["fluxRequired"]) => FLUX_REQUIRED,
+        (K::FvSchemes, [_]) => SCHEME_FAMILY,
+        (K::FvSolution, []) => FV_SOLUTION,
+        (K::FvSolution, ["solvers", _]) => LINEAR_SOLVER,
+        (K::FvSolution, ["SIMPLE"]) => SIMPLE,
+        (K::FvSolution, ["PIMPLE"]) => PIMPLE,
+        (K::FvSolution, ["PISO"]) => PISO,
+        (K::FvSolution, ["PIMPLE", "outerCorrectorResidualControl", _]) => OUTER_CORRECTOR_RESIDUAL,
+        (K::FvSolution, ["relaxationFactors"]) => RELAXATION_FACTORS,
+        (K::Field(_), []) => FIELD,
+        (K::Field(_), ["boundaryField", _]) => PATCH_FIELD,
+        (K::BlockMeshDict, []) => BLOCK_MESH_DICT,
+        (K::BlockMeshDict, [_]) => BLOCK_MESH_PATCH,
+        (K::DecomposeParDict, []) => DECOMPOSE_PAR_DICT,
+        (K::DecomposeParDict, ["simpleCoeffs" | "hierarchicalCoeffs"]) => DECOMPOSITION_COEFFS,
+        (K::PhysicalProperties, []) => PHYSICAL_PROPERTIES,
+        (K::MomentumTransport, []) => MOMENTUM_TRANSPORT,
+        (K::MomentumTransport, ["RAS"]) => RAS,
+        (K::MomentumTransport, ["LES"]) => LES,
+        (K::Gravity, []) => GRAVITY,
+        (K::SnappyHexMeshDict, []) => SNAPPY_HEX_MESH_DICT,
+        (K::SnappyHexMeshDict, ["castellatedMeshControls"]) => CASTELLATED_MESH_CONTROLS,
+        (K::SnappyHexMeshDict, ["snapControls"]) => SNAP_CONTROLS,
+        (K::SnappyHexMeshDict, ["addLayersControls"]) => ADD_LAYERS_CONTROLS,
+        _ => &[],
+    }
+}
+
+/// The keys particular to a linear `solver`, e.g. `smoother` for `GAMG`.
+pub fn solver_keys(solver: &str) -> &'static [Key] {
+    match solver {
+        "GAMG" => GAMG_SOLVER,
+        "PCG" | "PPCG" | "PPCR" => PCG_SOLVER,
+        "PBiCGStab" | "PBiCG" => PBICGSTAB_SOLVER,
+        "smoothSolver" => SMOOTH_SOLVER,
+        _ => &[],
+    }
+}
+
+/// What the keys of the dictionary at `path` are when they are named after fields, as the
+/// linear solvers in `fvSolution`. The name of the template is empty.
+pub fn field_key(kind: FileKind, path: &[&str]) -> Option<&'static Key> {
+    use FileKind as K;
+    Some(match (kind, path) {
+        (K::FvSolution, ["solvers"]) => &SOLVER_FIELD,
+        (K::FvSolution, ["SIMPLE" | "PIMPLE", "residualControl"]) => &RESIDUAL_CONTROL_FIELD,
+        (K::FvSolution, ["PIMPLE", "outerCorrectorResidualControl"]) => &OUTER_CORRECTOR_FIELD,
+        (K::FvSolution, ["relaxationFactors", "fields"]) => &FIELD_RELAXATION,
+        (K::FvSolution, ["relaxationFactors", "equations"]) => &EQUATION_RELAXATION,
+        _ => return None,
+    })
+}
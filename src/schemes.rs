@@ -0,0 +1,445 @@
+use crate::file_kind;
+
+/// A word that can come next in a scheme, with what it means.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub text: String,
+    pub doc: &'static str,
+}
+
+pub type Words = &'static [(&'static str, &'static str)];
+
+pub const DDT: Words = &[
+    ("Euler", "First order, bounded, implicit."),
+    (
+        "backward",
+        "Second order implicit, using the two previous time steps.",
+    ),
+    (
+        "CrankNicolson",
+        "Second order implicit, blended with Euler by the coefficient that follows.",
+    ),
+    ("steadyState", "No time derivative, for steady solvers."),
+    (
+        "localEuler",
+        "Euler with a local time step, for pseudo-transient solvers.",
+    ),
+    (
+        "bounded",
+        "Removes the continuity error from a steady or pseudo-transient scheme.",
+    ),
+];
+
+pub const GRAD: Words = &[
+    (
+        "Gauss",
+        "Integrates face values interpolated by the scheme that follows.",
+    ),
+    (
+        "leastSquares",
+        "Least squares fit to the neighbouring cells.",
+    ),
+    (
+        "pointCellsLeastSquares",
+        "Least squares fit to the cells sharing a point.",
+    ),
+    ("fourth", "Fourth order least squares."),
+];
+
+pub const GRAD_LIMITERS: Words = &[
+    (
+        "cellLimited",
+        "Limits the gradient so face values stay within the neighbouring cell values.",
+    ),
+    (
+        "cellMDLimited",
+        "cellLimited applied to each direction separately.",
+    ),
+    (
+        "faceLimited",
+        "Limits the gradient by the values of the faces.",
+    ),
+    (
+        "faceMDLimited",
+        "faceLimited applied to each direction separately.",
+    ),
+];
+
+/// Interpolation schemes without arguments.
+pub const INTERPOLATION: Words = &[
+    (
+        "linear",
+        "Central differencing, second order and unbounded.",
+    ),
+    ("upwind", "Upwind differencing, first order and bounded."),
+    ("midPoint", "Average of the two neighbouring cells."),
+    ("harmonic", "Harmonic mean of the two neighbouring cells."),
+    ("vanLeer", "TVD scheme with the van Leer limiter."),
+    (
+        "vanLeerV",
+        "vanLeer limited in the direction of steepest change.",
+    ),
+    ("MUSCL", "TVD scheme with the MUSCL limiter."),
+    ("Minmod", "TVD scheme with the Minmod limiter."),
+    ("SuperBee", "TVD scheme with the SuperBee limiter."),
+    ("QUICK", "Quadratic upwind, third order."),
+    ("UMIST", "TVD scheme with the UMIST limiter."),
+    ("cubic", "Cubic interpolation, fourth order and unbounded."),
+];
+
+/// Interpolation schemes followed by the gradient scheme of the field.
+pub const INTERPOLATION_WITH_GRAD: Words = &[
+    (
+        "linearUpwind",
+        "Upwind with an explicit correction by the gradient, second order.",
+    ),
+    (
+        "linearUpwindV",
+        "linearUpwind limited in the direction of steepest change.",
+    ),
+    ("LUST", "75% linear and 25% linearUpwind, for LES."),
+];
+
+/// Interpolation schemes followed by a limiter coefficient between 0 and 1.
+pub const INTERPOLATION_WITH_COEFFICIENT: Words = &[
+    (
+        "limitedLinear",
+        "linear limited towards upwind; 1 is the most limited.",
+    ),
+    (
+        "limitedLinearV",
+        "limitedLinear in the direction of steepest change.",
+    ),
+    ("limitedLinear01", "limitedLinear bounded between 0 and 1."),
+    ("limitedCubic", "cubic limited towards upwind."),
+];
+
+pub const SN_GRAD: Words = &[
+    ("corrected", "Explicit non-orthogonal correction."),
+    ("uncorrected", "No non-orthogonal correction."),
+    (
+        "limited",
+        "Non-orthogonal correction limited by the coefficient that follows.",
+    ),
+    ("orthogonal", "For orthogonal meshes, without correction."),
+    (
+        "faceCorrected",
+        "Non-orthogonal correction from the face gradient.",
+    ),
+];
+
+/// Solved fields without a convection term.
+const NOT_CONVECTED: &[&str] = &["p", "p_rgh", "pointDisplacement", "cellDisplacement"];
+
+enum Step<'a> {
+    /// The words typed so far form a complete scheme, followed by these
+    Done(&'a [&'a str]),
+    /// The scheme goes on with one of these
+    Next(Vec<Suggestion>),
+}
+
+/// The words that can follow `words` in the scheme for `term` in the `family` dictionary of
+/// `fvSchemes`, e.g. `Gauss` then `linear` then `corrected` for a laplacian.
+pub fn next_words(family: &str, term: &str, words: &[&str]) -> Vec<Suggestion> {
+    if term == "default" && words == ["none"] {
+        return Vec::new();
+    }
+
+    let field = term_field(term);
+    let step = match family {
+        "ddtSchemes" => ddt(words),
+        "gradSchemes" => grad(words, field),
+        "divSchemes" => div(words, field),
+        "laplacianSchemes" => laplacian(words),
+        "interpolationSchemes" => interpolation(words, field),
+        "snGradSchemes" => sn_grad(words),
+        _ => return Vec::new(),
+    };
+
+    match step {
+        Step::Next(mut suggestions) => {
+            if term == "default" && words.is_empty() {
+                suggestions.insert(0, suggestion("none", "Every term needs its own scheme."));
+            }
+            suggestions
+        }
+        Step::Done(_) => Vec::new(),
+    }
+}
+
+/// The terms a solver needs schemes for in the `family` dictionary, besides `default`, for
+/// a case with `fields` run by `application`.
+pub fn terms(family: &str, fields: &[&str], application: Option<&str>) -> Vec<String> {
+    let transported: Vec<_> = fields
+        .iter()
+        .filter(|field| file_kind::is_solved(field) && !NOT_CONVECTED.contains(field))
+        .collect();
+    let compressible = application.is_some_and(|application| {
+        application.starts_with("rho") || application.starts_with("buoyant")
+    });
+    let has = |name: &str| fields.contains(&name);
+
+    let mut terms = Vec::new();
+    match family {
+        "gradSchemes" => {
+            terms.extend(
+                fields
+                    .iter()
+                    .filter(|field| transported.contains(field) || matches!(**field, "p" | "p_rgh"))
+                    .map(|field| format!("grad({field})")),
+            );
+        }
+        "divSchemes" => {
+            // Every phase fraction is convected by the same two terms
+            if transported.iter().any(|field| field.starts_with("alpha.")) {
+                terms.push("div(phi,alpha)".to_string());
+                terms.push("div(phirb,alpha)".to_string());
+            }
+            terms.extend(
+                transported
+                    .iter()
+                    .filter(|field| !field.starts_with("alpha."))
+                    .map(|field| format!("div(phi,{field})")),
+            );
+            if compressible {
+                if has("T") {
+                    terms.push("div(phi,K)".to_string());
+                    terms.push("div(phi,h)".to_string());
+                }
+                terms.push("div(((rho*nuEff)*dev2(T(grad(U)))))".to_string());
+            } else if has("U") && has("nut") {
+                terms.push("div((nuEff*dev2(T(grad(U)))))".to_string());
+            }
+        }
+        "fluxRequired" => {
+            terms.extend(
+                ["p", "p_rgh"]
+                    .into_iter()
+                    .filter(|field| has(field))
+                    .map(str::to_string),
+            );
+        }
+        _ => {}
+    }
+    terms
+}
+
+/// The field a term applies to, e.g. `U` for `div(phi,U)`, used for `grad(U)` arguments.
+fn term_field(term: &str) -> &str {
+    let inner = term
+        .split_once('(')
+        .map_or("", |(_, inner)| inner.trim_end_matches(')'));
+    match inner.rsplit_once(',') {
+        Some((_, field)) => field,
+        None if !inner.is_empty() && !inner.contains('(') => inner,
+        None => "U",
+    }
+}
+
+fn suggestion(text: &str, doc: &'static str) -> Suggestion {
+    Suggestion {
+        text: text.to_string(),
+        doc,
+    }
+}
+
+fn suggestions(lists: &[Words]) -> Vec<Suggestion> {
+    lists
+        .iter()
+        .flat_map(|list| list.iter())
+        .map(|(text, doc)| suggestion(text, doc))
+        .collect()
+}
+
+fn contains(list: Words, word: &str) -> bool {
+    list.iter().any(|(name, _)| *name == word)
+}
+
+fn ddt<'a>(words: &'a [&'a str]) -> Step<'a> {
+    match words {
+        [] => Step::Next(suggestions(&[DDT])),
+        ["bounded", rest @ ..] => ddt(rest),
+        ["CrankNicolson"] => Step::Next(vec![suggestion(
+            "0.9",
+            "Off-centering coefficient: 1 is Crank-Nicolson, 0 is Euler.",
+        )]),
+        ["CrankNicolson", _, rest @ ..] | [_, rest @ ..] => Step::Done(rest),
+    }
+}
+
+fn grad<'a>(words: &'a [&'a str], field: &str) -> Step<'a> {
+    match words {
+        [] => Step::Next(suggestions(&[GRAD, GRAD_LIMITERS])),
+        [limiter, rest @ ..] if contains(GRAD_LIMITERS, limiter) => match grad(rest, field) {
+            Step::Done([]) => Step::Next(vec![suggestion(
+                "1",
+                "Limiter coefficient: 1 bounds fully, 0 not at all.",
+            )]),
+            Step::Done([_, rest @ ..]) => Step::Done(rest),
+            next => next,
+        },
+        ["Gauss", rest @ ..] => interpolation(rest, field),
+        [_, rest @ ..] => Step::Done(rest),
+    }
+}
+
+fn div<'a>(words: &'a [&'a str], field: &str) -> Step<'a> {
+    match words {
+        [] => Step::Next(vec![
+            suggestion("Gauss", GRAD[0].1),
+            suggestion(
+                "bounded",
+                "Removes the continuity error, for steady solvers.",
+            ),
+        ]),
+        ["bounded"] => Step::Next(vec![suggestion("Gauss", GRAD[0].1)]),
+        ["bounded", "Gauss", rest @ ..] | ["Gauss", rest @ ..] => interpolation(rest, field),
+        [_, rest @ ..] => Step::Done(rest),
+    }
+}
+
+fn laplacian<'a>(words: &'a [&'a str]) -> Step<'a> {
+    match words {
+        [] => Step::Next(vec![suggestion("Gauss", GRAD[0].1)]),
+        ["Gauss", rest @ ..] => match interpolation_without_arguments(rest) {
+            Step::Done(rest) => sn_grad(rest),
+            next => next,
+        },
+        [_, rest @ ..] => Step::Done(rest),
+    }
+}
+
+fn interpolation<'a>(words: &'a [&'a str], field: &str) -> Step<'a> {
+    match words {
+        [] => Step::Next(suggestions(&[
+            INTERPOLATION,
+            INTERPOLATION_WITH_GRAD,
+            INTERPOLATION_WITH_COEFFICIENT,
+        ])),
+        [scheme] if contains(INTERPOLATION_WITH_GRAD, scheme) => Step::Next(vec![Suggestion {
+            text: format!("grad({field})"),
+            doc: "Gradient scheme of the field, as named in gradSchemes.",
+        }]),
+        [scheme] if contains(INTERPOLATION_WITH_COEFFICIENT, scheme) => {
+            Step::Next(vec![suggestion(
+                "1",
+                "Limiter coefficient: 1 is the most limited, 0 is linear.",
+            )])
+        }
+        [scheme, _, rest @ ..]
+            if contains(INTERPOLATION_WITH_GRAD, scheme)
+                || contains(INTERPOLATION_WITH_COEFFICIENT, scheme) =>
+        {
+            Step::Done(rest)
+        }
+        [_, rest @ ..] => Step::Done(rest),
+    }
+}
+
+/// The interpolation of the diffusivity in a laplacian, which takes no arguments.
+fn interpolation_without_arguments<'a>(words: &'a [&'a str]) -> Step<'a> {
+    match words {
+        [] => Step::Next(suggestions(&[INTERPOLATION])),
+        [_, rest @ ..] => Step::Done(rest),
+    }
+}
+
+fn sn_grad<'a>(words: &'a [&'a str]) -> Step<'a> {
+    let coefficient = || {
+        suggestion(
+            "0.33",
+            "Limiter coefficient: 1 is corrected, 0 is uncorrected.",
+        )
+    };
+    match words {
+        [] => Step::Next(suggestions(&[SN_GRAD])),
+        ["limited"] => Step::Next(vec![suggestion("corrected", SN_GRAD[0].1), coefficient()]),
+        ["limited", "corrected"] => Step::Next(vec![coefficient()]),
+        ["limited", "corrected", _, rest @ ..] | ["limited", _, rest @ ..] | [_, rest @ ..] => {
+            Step::Done(rest)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(family: &str, term: &str, words: &[&str]) -> Vec<String> {
+        next_words(family, term, words)
+            .into_iter()
+            .map(|suggestion| suggestion.text)
+            .collect()
+    }
+
+    #[test]
+    fn test_next_words_follow_the_grammar() {
+        assert_eq!(texts("divSchemes", "div(phi,U)", &[]), ["Gauss", "bounded"]);
+        assert!(
+            texts("divSchemes", "div(phi,U)", &["bounded", "Gauss"])
+                .contains(&"linearUpwind".to_string())
+        );
+        assert_eq!(
+            texts("divSchemes", "div(phi,k)", &["Gauss", "linearUpwind"]),
+            ["grad(k)"]
+        );
+        assert!(
+            texts(
+                "divSchemes",
+                "div(phi,U)",
+                &["Gauss", "linearUpwind", "grad(U)"]
+            )
+            .is_empty()
+        );
+
+        assert!(
+            texts("laplacianSchemes", "default", &["Gauss", "linear"])
+                .contains(&"corrected".to_string())
+        );
+        assert_eq!(
+            texts(
+                "laplacianSchemes",
+                "default",
+                &["Gauss", "linear", "limited", "corrected"]
+            ),
+            ["0.33"]
+        );
+        assert_eq!(
+            texts(
+                "gradSchemes",
+                "grad(U)",
+                &["cellLimited", "Gauss", "linear"]
+            ),
+            ["1"]
+        );
+        assert_eq!(texts("ddtSchemes", "default", &[])[0], "none");
+        assert!(texts("ddtSchemes", "default", &["none"]).is_empty());
+    }
+
+    #[test]
+    fn test_terms_follow_the_fields() {
+        let fields = ["U", "epsilon", "k", "nut", "p"];
+        assert_eq!(
+            terms("divSchemes", &fields, Some("simpleFoam")),
+            [
+                "div(phi,U)",
+                "div(phi,epsilon)",
+                "div(phi,k)",
+                "div((nuEff*dev2(T(grad(U)))))"
+            ]
+        );
+        assert_eq!(terms("fluxRequired", &fields, None), ["p"]);
+        assert_eq!(
+            terms("divSchemes", &["U", "p"], Some("icoFoam")),
+            ["div(phi,U)"]
+        );
+        assert_eq!(
+            terms(
+                "divSchemes",
+                &["U", "alpha.air", "alpha.water"],
+                Some("multiphaseEulerFoam")
+            ),
+            ["div(phi,alpha)", "div(phirb,alpha)", "div(phi,U)"]
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use tower_lsp::lsp_types::{Position, SelectionRange};
+
+use crate::ast::{self, Dictionary, Entry, Value};
+use crate::document::Document;
+use crate::parser::Span;
+
+/// For each position, the ranges expand-selection steps through: the word or number under
+/// the cursor, the lists around it, its entry, the enclosing dictionary entries and finally
+/// the whole file.
+pub fn selection_ranges(document: &Document, positions: &[Position]) -> Vec<SelectionRange> {
+    positions
+        .iter()
+        .map(|position| {
+            let mut spans = vec![document.ast.span];
+            in_dictionary(&document.ast, document.offset_at(*position), &mut spans);
+            spans.dedup();
+
+            let mut selection: Option<SelectionRange> = None;
+            for span in spans {
+                selection = Some(SelectionRange {
+                    range: document.range_of(span),
+                    parent: selection.map(Box::new),
+                });
+            }
+            selection.expect("the file span is always there")
+        })
+        .collect()
+}
+
+fn in_dictionary(dict: &Dictionary, offset: usize, spans: &mut Vec<Span>) {
+    let Some(entry) = dict
+        .entries
+        .iter()
+        .find(|entry| ast::contains(entry.span(), offset))
+    else {
+        return;
+    };
+    spans.push(entry.span());
+    match entry {
+        Entry::Dict { key, .. } if ast::contains(key.span, offset) => spans.push(key.span),
+        Entry::Dict { dict, .. } => in_dictionary(dict, offset, spans),
+        Entry::Value { key, .. } if ast::contains(key.span, offset) => spans.push(key.span),
+        Entry::Value { values, .. } | Entry::Bare { values, .. } => {
+            in_values(values, offset, spans)
+        }
+        Entry::Directive { name, args, .. } => {
+            if ast::contains(name.span, offset) {
+                spans.push(name.span);
+            } else {
+                in_values(args, offset, spans);
+            }
+        }
+        Entry::Macro { name, .. } => spans.push(name.span),
+    }
+}
+
+/// Dictionaries in lists are selected together with the word naming them before their braces.
+fn in_values(values: &[Value], offset: usize, spans: &mut Vec<Span>) {
+    let Some(index) = values
+        .iter()
+        .position(|value| ast::contains(value.span(), offset))
+    else {
+        return;
+    };
+    let value = &values[index];
+    let named = match index.checked_sub(1).map(|previous| &values[previous]) {
+        Some(Value::Word(name)) if matches!(value, Value::Dict(_)) => Some(name),
+        _ => None,
+    };
+    if let Some(name) = named {
+        spans.push(Span {
+            start: name.span.start,
+            end: value.span().end,
+        });
+    }
+    spans.push(value.span());
+    match value {
+        Value::List { items, .. } | Value::Dimensions { items, .. } => {
+            in_values(items, offset, spans)
+        }
+        Value::Dict(dict) => in_dictionary(dict, offset, spans),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_kind::FileKind;
+
+    fn selections(text: &str, kind: FileKind) -> Vec<String> {
+        let cursor = text.find('|').unwrap();
+        let text = text.replace('|', "");
+        let document = Document::new(text.clone(), 0, kind);
+        let selection = selection_ranges(&document, &[document.position_at(cursor)]);
+        let mut texts = Vec::new();
+        let mut selection = selection.first();
+        while let Some(range) = selection {
+            let start = document.offset_at(range.range.start);
+            let end = document.offset_at(range.range.end);
+            texts.push(text[start..end].to_string());
+            selection = range.parent.as_deref();
+        }
+        texts
+    }
+
+    #[test]
+    fn test_selection_expands_from_a_number_to_the_file() {
+        let text =
+            "boundaryField\n{\n    inlet\n    {\n        value uniform (1 |0 0);\n    }\n}\n";
+        assert_eq!(
+            selections(text, FileKind::Field(None)),
+            [
+                "0",
+                "(1 0 0)",
+                "value uniform (1 0 0);",
+                "inlet\n    {\n        value uniform (1 0 0);\n    }",
+                "boundaryField\n{\n    inlet\n    {\n        value uniform (1 0 0);\n    }\n}",
+                "boundaryField\n{\n    inlet\n    {\n        value uniform (1 0 0);\n    }\n}\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_selection_of_patches_in_lists() {
+        let text = "boundary\n(\n    walls\n    {\n        type |wall;\n    }\n);\n";
+        assert_eq!(
+            selections(text, FileKind::BlockMeshDict)[..4],
+            [
+                "wall",
+                "type wall;",
+                "{\n        type wall;\n    }",
+                "walls\n    {\n        type wall;\n    }",
+            ]
+        );
+    }
+}
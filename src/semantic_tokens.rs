@@ -0,0 +1,491 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use dashmap::DashMap;
+use tower_lsp::lsp_types::{
+    Position, Range, SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens,
+    SemanticTokensEdit, SemanticTokensLegend, Url,
+};
+
+use crate::ast::{Dictionary, Entry, Value, Word};
+use crate::document::Document;
+use crate::file_kind::FileKind;
+use crate::parser::{Span, Token};
+use crate::schema;
+
+/// Token types, in the order of the legend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Keyword,
+    Dictionary,
+    Property,
+    BoundaryType,
+    Field,
+    Patch,
+    Number,
+    Unit,
+    Macro,
+    Directive,
+    Regexp,
+    String,
+    Comment,
+}
+
+const KINDS: &[Kind] = &[
+    Kind::Keyword,
+    Kind::Dictionary,
+    Kind::Property,
+    Kind::BoundaryType,
+    Kind::Field,
+    Kind::Patch,
+    Kind::Number,
+    Kind::Unit,
+    Kind::Macro,
+    Kind::Directive,
+    Kind::Regexp,
+    Kind::String,
+    Kind::Comment,
+];
+
+impl Kind {
+    /// Standard LSP names where one fits; the others are contributed by the VS Code extension
+    /// with a standard super type.
+    fn name(self) -> SemanticTokenType {
+        match self {
+            Kind::Keyword => SemanticTokenType::KEYWORD,
+            Kind::Dictionary => SemanticTokenType::new("dictionary"),
+            Kind::Property => SemanticTokenType::PROPERTY,
+            Kind::BoundaryType => SemanticTokenType::new("boundaryType"),
+            Kind::Field => SemanticTokenType::new("field"),
+            Kind::Patch => SemanticTokenType::new("patch"),
+            Kind::Number => SemanticTokenType::NUMBER,
+            Kind::Unit => SemanticTokenType::new("unit"),
+            Kind::Macro => SemanticTokenType::MACRO,
+            Kind::Directive => SemanticTokenType::new("directive"),
+            Kind::Regexp => SemanticTokenType::REGEXP,
+            Kind::String => SemanticTokenType::STRING,
+            Kind::Comment => SemanticTokenType::COMMENT,
+        }
+    }
+}
+
+// Modifier bits, in the order of the legend
+const DEPRECATED: u32 = 1 << 0;
+const UNKNOWN: u32 = 1 << 1;
+const DEFAULT_LIBRARY: u32 = 1 << 2;
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: KINDS.iter().map(|kind| kind.name()).collect(),
+        token_modifiers: vec![
+            SemanticTokenModifier::DEPRECATED,
+            SemanticTokenModifier::new("unknown"),
+            SemanticTokenModifier::DEFAULT_LIBRARY,
+        ],
+    }
+}
+
+/// The semantic tokens of the whole document.
+pub fn full(document: &Document) -> Vec<SemanticToken> {
+    encode(document, &highlights(document))
+}
+
+/// The semantic tokens overlapping `range`.
+pub fn range(document: &Document, range: Range) -> Vec<SemanticToken> {
+    let start = document.offset_at(range.start);
+    let end = document.offset_at(range.end);
+    let highlights: Vec<_> = highlights(document)
+        .into_iter()
+        .filter(|highlight| highlight.span.start < end && start < highlight.span.end)
+        .collect();
+    encode(document, &highlights)
+}
+
+/// The edit turning `old` into `new`: everything between their common prefix and suffix.
+pub fn diff(old: &[SemanticToken], new: &[SemanticToken]) -> Vec<SemanticTokensEdit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let deleted = old.len() - prefix - suffix;
+    let inserted = &new[prefix..new.len() - suffix];
+    if deleted == 0 && inserted.is_empty() {
+        return Vec::new();
+    }
+
+    // Edits count the integers of the flattened encoding, five per token
+    vec![SemanticTokensEdit {
+        start: (prefix * 5) as u32,
+        delete_count: (deleted * 5) as u32,
+        data: Some(inserted.to_vec()),
+    }]
+}
+
+/// The last full result sent for each document, which `full/delta` requests diff against.
+#[derive(Debug, Default)]
+pub struct TokenCache {
+    next_id: AtomicU64,
+    results: DashMap<Url, SemanticTokens>,
+}
+
+impl TokenCache {
+    /// Remember `data` as the latest result for `uri`.
+    pub fn store(&self, uri: Url, data: Vec<SemanticToken>) -> SemanticTokens {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let tokens = SemanticTokens {
+            result_id: Some(id.to_string()),
+            data,
+        };
+        self.results.insert(uri, tokens.clone());
+        tokens
+    }
+
+    /// The tokens of an earlier result, if it is still the latest one for `uri`.
+    pub fn previous(&self, uri: &Url, result_id: &str) -> Option<Vec<SemanticToken>> {
+        let tokens = self.results.get(uri)?;
+        (tokens.result_id.as_deref() == Some(result_id)).then(|| tokens.data.clone())
+    }
+
+    pub fn forget(&self, uri: &Url) {
+        self.results.remove(uri);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Highlight {
+    span: Span,
+    kind: Kind,
+    modifiers: u32,
+}
+
+/// Everything worth colouring, ordered by position and without overlaps.
+fn highlights(document: &Document) -> Vec<Highlight> {
+    let mut highlighter = Highlighter {
+        kind: document.kind,
+        path: Vec::new(),
+        patches: Vec::new(),
+        highlights: Vec::new(),
+    };
+    highlighter.dictionary(&document.ast);
+
+    let comments = document
+        .tokens
+        .iter()
+        .zip(document.spans.iter())
+        .filter(|(token, _)| matches!(token, Token::BlockComment | Token::LineComment))
+        .map(|(_, span)| Highlight {
+            span: *span,
+            kind: Kind::Comment,
+            modifiers: 0,
+        });
+    let mut highlights = highlighter.highlights;
+    highlights.extend(comments);
+
+    highlights.sort_by_key(|highlight| (highlight.span.start, highlight.span.end));
+    let mut end = 0;
+    highlights.retain(|highlight| {
+        let keep = highlight.span.start >= end && highlight.span.start < highlight.span.end;
+        if keep {
+            end = highlight.span.end;
+        }
+        keep
+    });
+    highlights
+}
+
+/// Walks the tree, classifying keys and values by the dictionary they appear in.
+struct Highlighter<'a> {
+    kind: FileKind,
+    /// Keys of the enclosing dictionaries
+    path: Vec<&'a str>,
+    /// Whether each enclosing dictionary describes a patch
+    patches: Vec<bool>,
+    highlights: Vec<Highlight>,
+}
+
+impl<'a> Highlighter<'a> {
+    fn push(&mut self, span: Span, kind: Kind, modifiers: u32) {
+        self.highlights.push(Highlight {
+            span,
+            kind,
+            modifiers,
+        });
+    }
+
+    fn in_patch(&self) -> bool {
+        self.patches.last() == Some(&true)
+    }
+
+    /// Whether keys here name patches, as directly inside `boundaryField`.
+    fn in_patch_list(&self) -> bool {
+        matches!(self.kind, FileKind::Field(_)) && self.path == ["boundaryField"]
+    }
+
+    fn dictionary(&mut self, dict: &'a Dictionary) {
+        for entry in &dict.entries {
+            match entry {
+                Entry::Dict { key, dict, .. } => {
+                    let patch = self.in_patch_list();
+                    self.key(key, true);
+                    self.nested(&key.text, patch, dict);
+                }
+                Entry::Value { key, values, .. } => {
+                    self.key(key, false);
+                    self.values(&key.text, values);
+                }
+                Entry::Directive { name, args, .. } => {
+                    self.push(name.span, Kind::Directive, 0);
+                    self.values("", args);
+                }
+                Entry::Macro { name, .. } => self.push(name.span, Kind::Macro, 0),
+                Entry::Bare { values, .. } => self.values("", values),
+            }
+        }
+    }
+
+    fn nested(&mut self, name: &'a str, patch: bool, dict: &'a Dictionary) {
+        self.path.push(name);
+        self.patches.push(patch);
+        self.dictionary(dict);
+        self.path.pop();
+        self.patches.pop();
+    }
+
+    fn kind_of_key(&self, key: &Word, is_dict: bool) -> Kind {
+        if key.quoted {
+            Kind::Regexp
+        } else if key.text == "FoamFile" {
+            Kind::Keyword
+        } else if self.in_patch_list() {
+            Kind::Patch
+        } else if self.kind == FileKind::FvSolution && self.path == ["solvers"] {
+            Kind::Field
+        } else if is_dict {
+            Kind::Dictionary
+        } else {
+            Kind::Property
+        }
+    }
+
+    fn key(&mut self, key: &Word, is_dict: bool) {
+        let kind = self.kind_of_key(key, is_dict);
+        let modifiers = if schema::is_deprecated(&key.text) {
+            DEPRECATED
+        } else {
+            0
+        };
+
+        let is_scheme = self.kind == FileKind::FvSchemes
+            && matches!(self.path.as_slice(), [dict] if dict.ends_with("Schemes"));
+        if kind == Kind::Property && is_scheme && self.scheme_key(key) {
+            return;
+        }
+        self.push(key.span, kind, modifiers);
+    }
+
+    /// Split a scheme key such as `div(phi,U)` into the operator and the fields it applies
+    /// to. Returns `false` for keys that are not of that form.
+    fn scheme_key(&mut self, key: &Word) -> bool {
+        let Some((operator, arguments)) = key.text.split_once('(') else {
+            return false;
+        };
+        let Some(arguments) = arguments.strip_suffix(')') else {
+            return false;
+        };
+
+        let start = key.span.start;
+        self.push(
+            Span {
+                start,
+                end: start + operator.len(),
+            },
+            Kind::Property,
+            0,
+        );
+
+        let mut offset = start + operator.len() + 1;
+        for argument in arguments.split(',') {
+            let simple = argument
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && argument
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+            if simple {
+                let span = Span {
+                    start: offset,
+                    end: offset + argument.len(),
+                };
+                self.push(span, Kind::Field, 0);
+            }
+            offset += argument.len() + 1;
+        }
+        true
+    }
+
+    fn values(&mut self, key: &'a str, values: &'a [Value]) {
+        let mut values = values.iter().peekable();
+        while let Some(value) = values.next() {
+            match value {
+                Value::Word(word) => {
+                    // A name followed by a dictionary, as in the patch lists of
+                    // `polyMesh/boundary` and `blockMeshDict`
+                    if let Some(Value::Dict(dict)) = values.peek() {
+                        let patch = key == "boundary"
+                            || (self.kind == FileKind::Boundary && self.path.is_empty());
+                        let kind = if patch { Kind::Patch } else { Kind::Dictionary };
+                        self.push(word.span, kind, 0);
+                        values.next();
+                        self.nested(&word.text, patch, dict);
+                    } else {
+                        self.word(key, word);
+                    }
+                }
+                Value::Number(_, span) => self.push(*span, Kind::Number, 0),
+                Value::String(word) => self.push(word.span, Kind::String, 0),
+                Value::Macro(word) => self.push(word.span, Kind::Macro, 0),
+                Value::Directive(word) => self.push(word.span, Kind::Directive, 0),
+                Value::List { items, .. } => self.values(key, items),
+                Value::Dimensions { items, .. } => {
+                    for item in items {
+                        if let Value::Number(_, span) | Value::Word(Word { span, .. }) = item {
+                            self.push(*span, Kind::Unit, 0);
+                        }
+                    }
+                }
+                Value::Dict(dict) => self.nested(key, false, dict),
+                Value::Verbatim(_) | Value::Other(_) => {}
+            }
+        }
+    }
+
+    fn word(&mut self, key: &str, word: &Word) {
+        let text = word.text.as_str();
+        if key == "type" && self.in_patch() {
+            let modifiers = if schema::boundary_condition(text).is_some() {
+                DEFAULT_LIBRARY
+            } else {
+                UNKNOWN
+            };
+            self.push(word.span, Kind::BoundaryType, modifiers);
+        } else if key == "field"
+            || key == "fields"
+            || (key == "object"
+                && self.path == ["FoamFile"]
+                && matches!(self.kind, FileKind::Field(_)))
+        {
+            self.push(word.span, Kind::Field, 0);
+        } else if key == "patch" || key == "patches" {
+            self.push(word.span, Kind::Patch, 0);
+        } else if schema::is_deprecated(text) {
+            self.push(word.span, Kind::Keyword, DEPRECATED);
+        } else if schema::is_keyword(text) {
+            self.push(word.span, Kind::Keyword, DEFAULT_LIBRARY);
+        }
+    }
+}
+
+/// Encode highlights relative to each other, splitting those spanning lines as not every
+/// client supports multi-line tokens.
+fn encode(document: &Document, highlights: &[Highlight]) -> Vec<SemanticToken> {
+    let mut tokens = Vec::new();
+    let mut previous = Position::new(0, 0);
+
+    for highlight in highlights {
+        let first = document.position_at(highlight.span.start).line;
+        let last = document.position_at(highlight.span.end).line;
+        for line in first..=last {
+            let start = if line == first {
+                highlight.span.start
+            } else {
+                document.offset_at(Position::new(line, 0))
+            };
+            let end = if line == last {
+                highlight.span.end
+            } else {
+                document.offset_at(Position::new(line, u32::MAX))
+            };
+            if start >= end {
+                continue;
+            }
+
+            let position = document.position_at(start);
+            let delta_line = position.line - previous.line;
+            tokens.push(SemanticToken {
+                delta_line,
+                delta_start: if delta_line == 0 {
+                    position.character - previous.character
+                } else {
+                    position.character
+                },
+                length: document.text[start..end].encode_utf16().count() as u32,
+                token_type: highlight.kind as u32,
+                token_modifiers_bitset: highlight.modifiers,
+            });
+            previous = position;
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(text: &str, kind: FileKind) -> Vec<(String, Kind, u32)> {
+        let document = Document::new(text.to_string(), 0, kind);
+        highlights(&document)
+            .into_iter()
+            .map(|h| {
+                (
+                    text[h.span.start..h.span.end].to_string(),
+                    h.kind,
+                    h.modifiers,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_boundary_field_highlights() {
+        let text = "dimensions [0 1 -1 0 0 0 0];\nboundaryField\n{\n    inlet { type fixedValu; value uniform (1 0 0); }\n    \"(top|bottom)\" { type slip; }\n}\n";
+        let highlights = kinds(text, FileKind::Field(None));
+
+        assert!(highlights.contains(&("-1".to_string(), Kind::Unit, 0)));
+        assert!(highlights.contains(&("inlet".to_string(), Kind::Patch, 0)));
+        assert!(highlights.contains(&("fixedValu".to_string(), Kind::BoundaryType, UNKNOWN)));
+        assert!(highlights.contains(&("slip".to_string(), Kind::BoundaryType, DEFAULT_LIBRARY)));
+        assert!(highlights.contains(&("\"(top|bottom)\"".to_string(), Kind::Regexp, 0)));
+        assert!(highlights.contains(&("uniform".to_string(), Kind::Keyword, DEFAULT_LIBRARY)));
+    }
+
+    #[test]
+    fn test_scheme_keys_highlight_fields() {
+        let text = "divSchemes\n{\n    div(phi,U) Gauss linear;\n}\n";
+        let highlights = kinds(text, FileKind::FvSchemes);
+
+        assert!(highlights.contains(&("div".to_string(), Kind::Property, 0)));
+        assert!(highlights.contains(&("phi".to_string(), Kind::Field, 0)));
+        assert!(highlights.contains(&("U".to_string(), Kind::Field, 0)));
+    }
+
+    #[test]
+    fn test_multiline_comments_are_split_and_delta_encoded() {
+        let document = Document::new("/* a\nbc */\nx 1;\n".to_string(), 0, FileKind::Dictionary);
+        let tokens = full(&document);
+
+        let positions: Vec<_> = tokens
+            .iter()
+            .map(|t| (t.delta_line, t.delta_start, t.length))
+            .collect();
+        assert_eq!(positions, vec![(0, 0, 4), (1, 0, 5), (1, 0, 1), (0, 2, 1)]);
+
+        let edits = diff(&tokens, &tokens[..3]);
+        assert_eq!(edits.len(), 1);
+        assert_eq!((edits[0].start, edits[0].delete_count), (15, 5));
+    }
+}
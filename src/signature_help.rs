@@ -0,0 +1,315 @@
+use tower_lsp::lsp_types::{
+    Documentation, MarkupContent, MarkupKind, ParameterInformation, ParameterLabel, Position,
+    SignatureHelp, SignatureInformation,
+};
+
+use crate::ast::{Entry, Value};
+use crate::completion::Context;
+use crate::document::Document;
+use crate::file_kind::FileKind;
+use crate::schemes::{self, Words};
+use Parameter::{Any, OneOf, Word};
+
+/// One positional word of an entry.
+#[derive(Debug)]
+enum Parameter {
+    /// This very word
+    Word(&'static str, &'static str),
+    /// One of a few words
+    OneOf(&'static str, &'static str, &'static [Words]),
+    /// Anything, such as a number
+    Any(&'static str, &'static str),
+}
+
+impl Parameter {
+    fn label(&self) -> &'static str {
+        match self {
+            Word(label, _) | OneOf(label, _, _) | Any(label, _) => label,
+        }
+    }
+
+    fn doc(&self) -> &'static str {
+        match self {
+            Word(_, doc) | OneOf(_, doc, _) | Any(_, doc) => doc,
+        }
+    }
+
+    /// How well `word` fits, or `None` if it does not.
+    fn fit(&self, word: &str) -> Option<usize> {
+        match self {
+            Word(label, _) => (*label == word).then_some(2),
+            OneOf(_, _, lists) => lists
+                .iter()
+                .any(|list| list.iter().any(|(name, _)| *name == word))
+                .then_some(1),
+            Any(..) => Some(0),
+        }
+    }
+}
+
+type Signature = &'static [Parameter];
+
+const GAUSS: Parameter = Word("Gauss", "Gauss integration of the face values.");
+const CONVECTION: Parameter = OneOf(
+    "<interpolation>",
+    "Interpolation of the cell values to the faces, e.g. `linear` or `linearUpwind`.",
+    &[
+        schemes::INTERPOLATION,
+        schemes::INTERPOLATION_WITH_GRAD,
+        schemes::INTERPOLATION_WITH_COEFFICIENT,
+    ],
+);
+const ARGUMENT: Parameter = Any(
+    "[<argument>]",
+    "The gradient scheme of `linearUpwind` and `LUST`, or the coefficient of `limitedLinear`.",
+);
+const DIFFUSIVITY: Parameter = OneOf(
+    "<interpolation>",
+    "Interpolation of the diffusivity to the faces, usually `linear`.",
+    &[schemes::INTERPOLATION],
+);
+const SN_GRAD: Parameter = OneOf(
+    "<snGrad>",
+    "Surface normal gradient scheme, e.g. `corrected`.",
+    &[schemes::SN_GRAD],
+);
+const LIMITED: Parameter = Word("limited", "Limited non-orthogonal correction.");
+const CORRECTED: Parameter = Word("corrected", "Corrected scheme being limited.");
+const SN_GRAD_COEFFICIENT: Parameter = Any(
+    "<ψ>",
+    "Limiter coefficient between 0 (uncorrected) and 1 (corrected); 0.33 and 0.5 are usual.",
+);
+const DDT: Parameter = OneOf("<scheme>", "Time scheme, e.g. `Euler`.", &[schemes::DDT]);
+const LIMITER: Parameter = OneOf(
+    "<limiter>",
+    "Gradient limiter, e.g. `cellLimited`.",
+    &[schemes::GRAD_LIMITERS],
+);
+const GRAD: Parameter = OneOf(
+    "<scheme>",
+    "Gradient scheme, e.g. `leastSquares`.",
+    &[schemes::GRAD],
+);
+const GRAD_COEFFICIENT: Parameter = Any(
+    "<ψ>",
+    "Limiter coefficient between 0 (unlimited) and 1 (fully limited).",
+);
+
+const DDT_SIGNATURES: &[Signature] = &[
+    &[DDT],
+    &[
+        Word("CrankNicolson", "Crank-Nicolson, blended with Euler."),
+        Any(
+            "<ψ>",
+            "Off-centering coefficient between 0 (Euler) and 1 (Crank-Nicolson); 0.9 is usual.",
+        ),
+    ],
+    &[Word("bounded", "Removes the continuity error."), DDT],
+];
+
+const GRAD_SIGNATURES: &[Signature] = &[
+    &[GAUSS, DIFFUSIVITY],
+    &[GRAD],
+    &[LIMITER, GAUSS, DIFFUSIVITY, GRAD_COEFFICIENT],
+    &[LIMITER, GRAD, GRAD_COEFFICIENT],
+];
+
+const DIV_SIGNATURES: &[Signature] = &[
+    &[GAUSS, CONVECTION, ARGUMENT],
+    &[
+        Word(
+            "bounded",
+            "Removes the continuity error, for steady solvers.",
+        ),
+        GAUSS,
+        CONVECTION,
+        ARGUMENT,
+    ],
+];
+
+const LAPLACIAN_SIGNATURES: &[Signature] = &[
+    &[GAUSS, DIFFUSIVITY, SN_GRAD],
+    &[GAUSS, DIFFUSIVITY, LIMITED, CORRECTED, SN_GRAD_COEFFICIENT],
+    &[GAUSS, DIFFUSIVITY, LIMITED, SN_GRAD_COEFFICIENT],
+];
+
+const INTERPOLATION_SIGNATURES: &[Signature] = &[&[CONVECTION, ARGUMENT]];
+
+const SN_GRAD_SIGNATURES: &[Signature] = &[
+    &[SN_GRAD],
+    &[LIMITED, CORRECTED, SN_GRAD_COEFFICIENT],
+    &[LIMITED, SN_GRAD_COEFFICIENT],
+];
+
+const FUNCTION1_SIGNATURES: &[Signature] = &[
+    &[
+        Word("constant", "The same value at all times."),
+        Any("<value>", "The value, a scalar or a vector like the entry."),
+    ],
+    &[
+        Word("table", "Linear interpolation between points."),
+        Any(
+            "((<x> <value>) …)",
+            "The points, as pairs of time and value.",
+        ),
+    ],
+    &[
+        Word("polynomial", "Sum of powers of time."),
+        Any(
+            "((<coefficient> <exponent>) …)",
+            "The terms, as pairs of coefficient and exponent.",
+        ),
+    ],
+    &[Word("zero", "Zero at all times.")],
+    &[Word("one", "One at all times.")],
+];
+
+/// Entries whose value is a `Function1` of time, besides those starting with one of the
+/// `Function1` types.
+const FUNCTION1_KEYS: &[&str] = &[
+    "uniformValue",
+    "uniformGradient",
+    "uniformInletValue",
+    "volumetricFlowRate",
+    "massFlowRate",
+    "flowRate",
+    "meanVelocity",
+];
+
+/// The grammar of the entry under the cursor, with the word being typed highlighted.
+pub fn signature_help(document: &Document, position: Position) -> Option<SignatureHelp> {
+    let offset = document.offset_at(position);
+    let context = Context::at(document, offset);
+    if context.is_key_position() {
+        return None;
+    }
+    let Some(Entry::Value { key, values, span }) = context.entry else {
+        return None;
+    };
+    if document.text[span.start..offset].contains(';') {
+        return None;
+    }
+
+    let signatures = match (document.kind, context.path.as_slice()) {
+        (FileKind::FvSchemes, ["ddtSchemes"]) => DDT_SIGNATURES,
+        (FileKind::FvSchemes, ["gradSchemes"]) => GRAD_SIGNATURES,
+        (FileKind::FvSchemes, ["divSchemes"]) => DIV_SIGNATURES,
+        (FileKind::FvSchemes, ["laplacianSchemes"]) => LAPLACIAN_SIGNATURES,
+        (FileKind::FvSchemes, ["interpolationSchemes"]) => INTERPOLATION_SIGNATURES,
+        (FileKind::FvSchemes, ["snGradSchemes"]) => SN_GRAD_SIGNATURES,
+        _ if is_function1(&key.text, values) => FUNCTION1_SIGNATURES,
+        _ => return None,
+    };
+
+    let words = context.words_before(document);
+    let fitting: Vec<_> = signatures
+        .iter()
+        .filter_map(|signature| Some((*signature, fit(signature, &words)?)))
+        .collect();
+    let best = fitting
+        .iter()
+        .enumerate()
+        .max_by_key(|(index, (_, score))| (*score, std::cmp::Reverse(*index)))?
+        .0;
+
+    Some(SignatureHelp {
+        signatures: fitting
+            .iter()
+            .map(|(signature, _)| information(&key.text, signature))
+            .collect(),
+        active_signature: Some(best as u32),
+        active_parameter: Some(words.len() as u32),
+    })
+}
+
+fn is_function1(key: &str, values: &[Value]) -> bool {
+    let starts_with_type = match values.first() {
+        Some(Value::Word(word)) => FUNCTION1_SIGNATURES
+            .iter()
+            .any(|signature| signature[0].label() == word.text),
+        _ => false,
+    };
+    FUNCTION1_KEYS.contains(&key) || starts_with_type
+}
+
+/// How well the words before the cursor fit `signature`, if it has room for another.
+fn fit(signature: Signature, words: &[&str]) -> Option<usize> {
+    if signature.len() <= words.len() {
+        return None;
+    }
+    signature
+        .iter()
+        .zip(words)
+        .map(|(parameter, word)| parameter.fit(word))
+        .sum()
+}
+
+fn information(key: &str, signature: Signature) -> SignatureInformation {
+    let mut label = key.to_string();
+    let mut parameters = Vec::new();
+    for parameter in signature {
+        label.push(' ');
+        let start = label.encode_utf16().count() as u32;
+        label.push_str(parameter.label());
+        let end = label.encode_utf16().count() as u32;
+        parameters.push(ParameterInformation {
+            label: ParameterLabel::LabelOffsets([start, end]),
+            documentation: Some(Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: parameter.doc().to_string(),
+            })),
+        });
+    }
+    SignatureInformation {
+        label,
+        documentation: None,
+        parameters: Some(parameters),
+        active_parameter: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn help(text: &str, kind: FileKind) -> Option<(String, u32)> {
+        let cursor = text.find('|').unwrap();
+        let document = Document::new(text.replace('|', ""), 0, kind);
+        let help = signature_help(&document, document.position_at(cursor))?;
+        let signature = &help.signatures[help.active_signature? as usize];
+        Some((signature.label.clone(), help.active_parameter?))
+    }
+
+    #[test]
+    fn test_scheme_signatures_follow_the_words() {
+        let text = "laplacianSchemes\n{\n    laplacian(nuEff,U) Gauss linear |\n}\n";
+        assert_eq!(
+            help(text, FileKind::FvSchemes),
+            Some((
+                "laplacian(nuEff,U) Gauss <interpolation> <snGrad>".to_string(),
+                2
+            ))
+        );
+
+        let text = "laplacianSchemes\n{\n    default Gauss linear limited corrected |;\n}\n";
+        assert_eq!(
+            help(text, FileKind::FvSchemes),
+            Some((
+                "default Gauss <interpolation> limited corrected <ψ>".to_string(),
+                4
+            ))
+        );
+
+        let text = "divSchemes\n{\n    default none;|\n}\n";
+        assert_eq!(help(text, FileKind::FvSchemes), None);
+    }
+
+    #[test]
+    fn test_function1_signatures() {
+        let text = "boundaryField\n{\n    inlet\n    {\n        type uniformFixedValue;\n        uniformValue table |\n    }\n}\n";
+        assert_eq!(
+            help(text, FileKind::Field(None)),
+            Some(("uniformValue table ((<x> <value>) …)".to_string(), 1))
+        );
+    }
+}
@@ -0,0 +1,528 @@
+use std::ops::Deref;
+use std::path::Path;
+
+use tower_lsp::lsp_types::{DocumentSymbol, Location, Range, SymbolInformation, SymbolKind, Url};
+
+use crate::ast::{self, Dictionary, Entry, Value};
+use crate::case::Case;
+use crate::document::Document;
+use crate::file_kind::FileKind;
+use crate::parser::Span;
+
+/// Words starting an item of a `blockMeshDict` list, such as `hex` in `blocks` or `arc` in
+/// `edges`. Other words, such as `simpleGrading` or a zone name, belong to the item.
+const ITEM_WORDS: &[&str] = &[
+    "hex",
+    "arc",
+    "spline",
+    "polyLine",
+    "BSpline",
+    "line",
+    "project",
+    "projectCurve",
+];
+
+/// Values longer than this are cut short in the detail of a symbol.
+const MAX_DETAIL_LENGTH: usize = 40;
+
+/// The outline of a document: its dictionaries and entries, nested as in the file.
+pub fn document_symbols(document: &Document) -> Vec<DocumentSymbol> {
+    let outliner = Outliner { document };
+    outliner.dictionary(&document.ast, &mut Vec::new())
+}
+
+struct Outliner<'a> {
+    document: &'a Document,
+}
+
+impl<'a> Outliner<'a> {
+    fn dictionary(&self, dict: &'a Dictionary, path: &mut Vec<&'a str>) -> Vec<DocumentSymbol> {
+        dict.entries
+            .iter()
+            .flat_map(|entry| self.entry(entry, path))
+            .collect()
+    }
+
+    fn entry(&self, entry: &'a Entry, path: &mut Vec<&'a str>) -> Vec<DocumentSymbol> {
+        match entry {
+            Entry::Dict { key, dict, span } => {
+                path.push(&key.text);
+                let children = self.dictionary(dict, path);
+                path.pop();
+                let kind = self.dict_kind(path);
+                vec![self.symbol(&key.text, type_of(dict), kind, *span, key.span, children)]
+            }
+            Entry::Value { key, values, span } => {
+                path.push(&key.text);
+                let children = self.values(values, path);
+                path.pop();
+                let (kind, detail) = match values.as_slice() {
+                    [Value::List { .. }] => (SymbolKind::ARRAY, None),
+                    _ => (SymbolKind::PROPERTY, self.detail(values)),
+                };
+                vec![self.symbol(&key.text, detail, kind, *span, key.span, children)]
+            }
+            Entry::Directive { name, args, span } => {
+                let kind = if name.text.starts_with("#include") {
+                    SymbolKind::FILE
+                } else {
+                    SymbolKind::OPERATOR
+                };
+                let detail = self.detail(args);
+                vec![self.symbol(&name.text, detail, kind, *span, name.span, Vec::new())]
+            }
+            Entry::Macro { name, span } => vec![self.symbol(
+                &name.text,
+                None,
+                SymbolKind::VARIABLE,
+                *span,
+                name.span,
+                Vec::new(),
+            )],
+            // The patch list of `polyMesh/boundary` has no key, so its patches go on top
+            Entry::Bare { values, .. } => self.values(values, path),
+        }
+    }
+
+    /// Symbols for the dictionaries and word-led groups in a list, such as the patches of
+    /// `boundary` or the `hex` blocks of `blocks`.
+    fn values(&self, values: &'a [Value], path: &mut Vec<&'a str>) -> Vec<DocumentSymbol> {
+        let mut symbols = Vec::new();
+        for value in values {
+            if let Value::List { items, .. } = value {
+                symbols.extend(self.list(items, path));
+            }
+        }
+        symbols
+    }
+
+    fn list(&self, items: &'a [Value], path: &mut Vec<&'a str>) -> Vec<DocumentSymbol> {
+        let leader = match items.first() {
+            Some(Value::Word(word)) => word.text.as_str(),
+            _ => "",
+        };
+        let starts_item = |value: &Value| match value {
+            Value::Word(word) => word.text == leader || ITEM_WORDS.contains(&word.text.as_str()),
+            _ => false,
+        };
+        let mut symbols = Vec::new();
+        let mut index = 0;
+        let mut rest = items;
+        while let [first, tail @ ..] = rest {
+            match (first, tail) {
+                (Value::Word(name), [Value::Dict(dict), tail @ ..]) => {
+                    path.push(&name.text);
+                    let children = self.dictionary(dict, path);
+                    path.pop();
+                    let span = Span {
+                        start: name.span.start,
+                        end: dict.span.end,
+                    };
+                    symbols.push(self.symbol(
+                        &name.text,
+                        type_of(dict),
+                        self.dict_kind(path),
+                        span,
+                        name.span,
+                        children,
+                    ));
+                    rest = tail;
+                }
+                (Value::Word(name), _) if starts_item(first) => {
+                    let length = tail.iter().position(starts_item).unwrap_or(tail.len());
+                    let group = &tail[..length];
+                    let end = group.last().map_or(name.span.end, |last| last.span().end);
+                    symbols.push(self.symbol(
+                        &format!("{} #{index}", name.text),
+                        self.detail(group),
+                        SymbolKind::STRUCT,
+                        Span {
+                            start: name.span.start,
+                            end,
+                        },
+                        name.span,
+                        Vec::new(),
+                    ));
+                    index += 1;
+                    rest = &tail[length..];
+                }
+                _ => rest = tail,
+            }
+        }
+        symbols
+    }
+
+    /// Patches, function objects and plain dictionaries are told apart by where they are.
+    fn dict_kind(&self, path: &[&str]) -> SymbolKind {
+        match (self.document.kind, path) {
+            (FileKind::Field(_), ["boundaryField"])
+            | (FileKind::BlockMeshDict, ["boundary"])
+            | (FileKind::Boundary, []) => SymbolKind::INTERFACE,
+            (FileKind::ControlDict, ["functions"]) => SymbolKind::FUNCTION,
+            (_, []) => SymbolKind::NAMESPACE,
+            _ => SymbolKind::OBJECT,
+        }
+    }
+
+    /// The source text of `values` on one line, cut short if long.
+    fn detail(&self, values: &[Value]) -> Option<String> {
+        let first = values.first()?.span();
+        let last = values.last()?.span();
+        let text = &self.document.text[first.start..last.end];
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.chars().count() > MAX_DETAIL_LENGTH {
+            let cut: String = text.chars().take(MAX_DETAIL_LENGTH).collect();
+            Some(format!("{cut}…"))
+        } else {
+            Some(text)
+        }
+    }
+
+    fn range(&self, span: Span) -> Range {
+        Range::new(
+            self.document.position_at(span.start),
+            self.document.position_at(span.end),
+        )
+    }
+
+    fn symbol(
+        &self,
+        name: &str,
+        detail: Option<String>,
+        kind: SymbolKind,
+        span: Span,
+        selection: Span,
+        children: Vec<DocumentSymbol>,
+    ) -> DocumentSymbol {
+        #[allow(deprecated)]
+        DocumentSymbol {
+            name: name.to_string(),
+            detail,
+            kind,
+            tags: None,
+            deprecated: None,
+            range: self.range(span),
+            selection_range: self.range(selection),
+            children: (!children.is_empty()).then_some(children),
+        }
+    }
+}
+
+/// The `type` of a dictionary, shown next to patches and function objects.
+fn type_of(dict: &Dictionary) -> Option<String> {
+    Some(dict.get("type")?.word()?.text.clone())
+}
+
+/// Something a file gives a name to, which other files refer to.
+#[derive(Debug)]
+pub struct Definition<'a> {
+    pub name: &'a str,
+    pub kind: SymbolKind,
+    /// Where the name is
+    pub span: Span,
+}
+
+impl<'a> Definition<'a> {
+    fn new(name: &'a ast::Word, kind: SymbolKind) -> Self {
+        Self {
+            name: &name.text,
+            kind,
+            span: name.span,
+        }
+    }
+}
+
+/// The patches, function objects, zones, refinement regions and geometry a file defines.
+pub fn definitions(document: &Document) -> Vec<Definition<'_>> {
+    let ast = &document.ast;
+    let mut definitions = Vec::new();
+    match document.kind {
+        FileKind::Boundary => {
+            for entry in &ast.entries {
+                if let Entry::Bare { values, .. } = entry {
+                    definitions.extend(patches(values));
+                }
+            }
+        }
+        FileKind::BlockMeshDict => {
+            if let Some(Entry::Value { values, .. }) = ast.get("boundary") {
+                definitions.extend(patches(values));
+            }
+        }
+        FileKind::ControlDict => {
+            let functions = ast.dict("functions").map_or(&[][..], |dict| &dict.entries);
+            for entry in functions {
+                if let Entry::Dict { key, .. } = entry {
+                    definitions.push(Definition::new(key, SymbolKind::FUNCTION));
+                }
+            }
+        }
+        FileKind::SnappyHexMeshDict => {
+            for entry in ast.dict("geometry").map_or(&[][..], |dict| &dict.entries) {
+                if let Some(key) = entry.key() {
+                    definitions.push(Definition::new(key, SymbolKind::OBJECT));
+                }
+            }
+            let controls = ast.dict("castellatedMeshControls");
+            let regions = controls.and_then(|controls| controls.dict("refinementRegions"));
+            for entry in regions.map_or(&[][..], |dict| &dict.entries) {
+                if let Some(key) = entry.key() {
+                    definitions.push(Definition::new(key, SymbolKind::MODULE));
+                }
+            }
+            let surfaces = controls.and_then(|controls| controls.dict("refinementSurfaces"));
+            for entry in surfaces.map_or(&[][..], |dict| &dict.entries) {
+                if let Entry::Dict { dict, .. } = entry {
+                    let zones = ["cellZone", "faceZone"]
+                        .into_iter()
+                        .filter_map(|key| dict.get(key)?.word());
+                    definitions.extend(zones.map(|zone| Definition::new(zone, SymbolKind::STRUCT)));
+                }
+            }
+        }
+        FileKind::TopoSetDict => {
+            let Some(Entry::Value { values, .. }) = ast.get("actions") else {
+                return definitions;
+            };
+            let actions = values.iter().flat_map(|value| match value {
+                Value::List { items, .. } => items.as_slice(),
+                _ => &[],
+            });
+            for action in actions {
+                if let Value::Dict(dict) = action
+                    && type_of(dict).is_some_and(|kind| kind.contains("Zone"))
+                    && let Some(name) = dict.get("name").and_then(Entry::word)
+                {
+                    definitions.push(Definition::new(name, SymbolKind::STRUCT));
+                }
+            }
+        }
+        _ => {}
+    }
+    definitions
+}
+
+fn patches(values: &[Value]) -> impl Iterator<Item = Definition<'_>> {
+    ast::named_dicts(values)
+        .into_iter()
+        .map(|(name, _)| Definition::new(name, SymbolKind::INTERFACE))
+}
+
+/// The first `limit` symbols of `cases` matching `query`, those named exactly like a word of
+/// the query first and the rest by name, whichever case they come from.
+pub fn workspace_symbols<C: Deref<Target = Case>>(
+    cases: impl IntoIterator<Item = C>,
+    query: &str,
+    limit: usize,
+) -> Vec<SymbolInformation> {
+    let mut symbols: Vec<_> = cases
+        .into_iter()
+        .flat_map(|case| case_symbols(&case, query))
+        .collect();
+    let exact = |symbol: &SymbolInformation| {
+        !query
+            .split_whitespace()
+            .any(|word| word.eq_ignore_ascii_case(&symbol.name))
+    };
+    symbols.sort_by(|a, b| {
+        (exact(a), &a.name, &a.container_name).cmp(&(exact(b), &b.name, &b.container_name))
+    });
+    symbols.truncate(limit);
+    symbols
+}
+
+/// The symbols of a case matching `query`: what its files define, and its fields. Each word
+/// of the query must match the name or the case, so `outlet pitzDaily` finds the `outlet`
+/// patch of `pitzDaily`.
+fn case_symbols(case: &Case, query: &str) -> Vec<SymbolInformation> {
+    let case_name = case
+        .root
+        .file_name()
+        .map_or(String::new(), |name| name.to_string_lossy().into_owned());
+    let mut symbols = Vec::new();
+    for (path, document) in &case.files {
+        let Ok(uri) = Url::from_file_path(path) else {
+            continue;
+        };
+        let relative = path.strip_prefix(&case.root).unwrap_or(path);
+        let container = Path::new(&case_name).join(relative);
+        let container = container.to_string_lossy();
+
+        let mut add = |name: &str, kind: SymbolKind, span: Span| {
+            if !matches_query(query, &format!("{name} {case_name}")) {
+                return;
+            }
+            let range = Range::new(
+                document.position_at(span.start),
+                document.position_at(span.end),
+            );
+            #[allow(deprecated)]
+            symbols.push(SymbolInformation {
+                name: name.to_string(),
+                kind,
+                tags: None,
+                deprecated: None,
+                location: Location::new(uri.clone(), range),
+                container_name: Some(container.to_string()),
+            });
+        };
+
+        if let FileKind::Field(_) = document.kind
+            && let Some(name) = path.file_name()
+        {
+            add(&name.to_string_lossy(), SymbolKind::FIELD, Span::default());
+        }
+        for definition in definitions(document) {
+            add(definition.name, definition.kind, definition.span);
+        }
+    }
+    symbols
+}
+
+/// Whether every word of `query` is found in `text` in order, ignoring case, as editors match
+/// symbol names.
+fn matches_query(query: &str, text: &str) -> bool {
+    let text = text.to_lowercase();
+    query.split_whitespace().all(|word| {
+        let mut rest = text.chars();
+        word.to_lowercase()
+            .chars()
+            .all(|c| rest.by_ref().any(|other| other == c))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_kind::FieldClass;
+
+    fn outline(symbols: &[DocumentSymbol], depth: usize, lines: &mut Vec<String>) {
+        for symbol in symbols {
+            lines.push(format!(
+                "{}{} {:?}",
+                "  ".repeat(depth),
+                symbol.name,
+                symbol.kind
+            ));
+            outline(symbol.children.as_deref().unwrap_or(&[]), depth + 1, lines);
+        }
+    }
+
+    fn lines(text: &str, kind: FileKind) -> Vec<String> {
+        let document = Document::new(text.to_string(), 0, kind);
+        let mut lines = Vec::new();
+        outline(&document_symbols(&document), 0, &mut lines);
+        lines
+    }
+
+    #[test]
+    fn test_field_outline() {
+        let text = "dimensions [0 1 -1 0 0 0 0];\ninternalField uniform (0 0 0);\nboundaryField\n{\n    inlet\n    {\n        type fixedValue;\n        value uniform (1 0 0);\n    }\n    #include \"walls\"\n}\n";
+        let document = Document::new(
+            text.to_string(),
+            0,
+            FileKind::Field(Some(FieldClass::Vector)),
+        );
+        let symbols = document_symbols(&document);
+        let inlet = &symbols[2].children.as_ref().unwrap()[0];
+
+        assert_eq!(inlet.name, "inlet");
+        assert_eq!(inlet.detail.as_deref(), Some("fixedValue"));
+        assert_eq!(inlet.kind, SymbolKind::INTERFACE);
+        assert_eq!(
+            lines(text, FileKind::Field(Some(FieldClass::Vector))),
+            [
+                "dimensions Property",
+                "internalField Property",
+                "boundaryField Namespace",
+                "  inlet Interface",
+                "    type Property",
+                "    value Property",
+                "  #include File",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_workspace_symbols_match_names_and_cases() {
+        let case = Case::with_files(
+            "/cases/pitzDaily",
+            &[
+                (
+                    "constant/polyMesh/boundary",
+                    "2\n(\n    inlet { type patch; }\n    outlet { type patch; }\n)\n",
+                    FileKind::Boundary,
+                ),
+                (
+                    "system/controlDict",
+                    "functions\n{\n    forceCoeffs1 { type forceCoeffs; }\n}\n",
+                    FileKind::ControlDict,
+                ),
+                ("0/U", "boundaryField {}\n", FileKind::Field(None)),
+            ],
+        );
+
+        let names = |query: &str| -> Vec<String> {
+            workspace_symbols([&case], query, usize::MAX)
+                .into_iter()
+                .map(|symbol| format!("{} {}", symbol.name, symbol.container_name.unwrap()))
+                .collect()
+        };
+        assert_eq!(
+            names("outlet pitz"),
+            ["outlet pitzDaily/constant/polyMesh/boundary"]
+        );
+        assert_eq!(names("fc1"), ["forceCoeffs1 pitzDaily/system/controlDict"]);
+        assert_eq!(names("U")[0], "U pitzDaily/0/U");
+        assert!(names("outlet cavity").is_empty());
+    }
+
+    #[test]
+    fn test_workspace_symbols_are_ranked_across_cases() {
+        let cases: Vec<_> = ["/cases/cavity", "/cases/pitzDaily"]
+            .into_iter()
+            .map(|root| {
+                let field = FileKind::Field(None);
+                Case::with_files(
+                    root,
+                    &[("0/U", "", field), ("0/Ux", "", field), ("0/Uy", "", field)],
+                )
+            })
+            .collect();
+
+        let names = |limit: usize| -> Vec<String> {
+            workspace_symbols(&cases, "U", limit)
+                .into_iter()
+                .map(|symbol| format!("{} {}", symbol.name, symbol.container_name.unwrap()))
+                .collect()
+        };
+        assert_eq!(names(2), ["U cavity/0/U", "U pitzDaily/0/U"]);
+        assert_eq!(
+            names(4),
+            [
+                "U cavity/0/U",
+                "U pitzDaily/0/U",
+                "Ux cavity/0/Ux",
+                "Ux pitzDaily/0/Ux"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_outline() {
+        let text = "vertices ((0 0 0) (1 0 0));\nblocks\n(\n    hex (0 1 2 3 4 5 6 7) (20 20 1) simpleGrading (1 1 1)\n    hex (4 5 6 7 8 9 10 11) (20 20 1) simpleGrading (1 1 1)\n);\nboundary\n(\n    walls { type wall; faces ((0 1 2 3)); }\n);\n";
+        assert_eq!(
+            lines(text, FileKind::BlockMeshDict),
+            [
+                "vertices Array",
+                "blocks Array",
+                "  hex #0 Struct",
+                "  hex #1 Struct",
+                "boundary Array",
+                "  walls Interface",
+                "    type Property",
+                "    faces Array",
+            ]
+        );
+    }
+}